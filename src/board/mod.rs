@@ -0,0 +1,321 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::tetromino::kind::PieceKind;
+use crate::tetromino::tetromino::Tetromino;
+
+const MIN_WIDTH: usize = 4; // Narrowest board the I tetromino still fits across
+const MIN_VISIBLE_HEIGHT: usize = 4;
+const MIN_HIDDEN_HEIGHT: usize = 2; // Rows guideline tetrominoes need to spawn above the field
+
+/// Represents the dimensions of the board, chosen when the game is created
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardSize {
+    pub width: usize, // Columns of the playfield, not counting the walls
+    pub visible_height: usize,
+    pub hidden_height: usize, // Rows above the visible field where tetrominoes spawn
+    pub scale: usize,         // Rows and columns of text every cell is drawn with, 2 in big mode
+}
+
+impl Default for BoardSize {
+    fn default() -> Self {
+        BoardSize {
+            width: 10,
+            visible_height: 38,
+            hidden_height: 2,
+            scale: 1,
+        }
+    }
+}
+
+impl BoardSize {
+    /// Big mode plays on a board half as wide and half as tall, drawn twice as large
+    pub const BIG: BoardSize = BoardSize {
+        width: 5,
+        visible_height: 19,
+        hidden_height: 2,
+        scale: 2,
+    };
+
+    /// Returns the number of rows of the board, counting the hidden ones
+    pub fn height(&self) -> usize {
+        self.visible_height + self.hidden_height
+    }
+
+    /// Checks that tetrominoes can spawn and move on a board of this size
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width < MIN_WIDTH {
+            return Err(format!("The board must be at least {} wide", MIN_WIDTH));
+        }
+
+        if self.visible_height < MIN_VISIBLE_HEIGHT {
+            return Err(format!(
+                "The board must be at least {} tall",
+                MIN_VISIBLE_HEIGHT
+            ));
+        }
+
+        if self.hidden_height < MIN_HIDDEN_HEIGHT {
+            return Err(format!(
+                "The board needs at least {} hidden rows",
+                MIN_HIDDEN_HEIGHT
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for BoardSize {
+    type Err = String;
+
+    /// Reads a size written as WIDTHxHEIGHT, such as 10x20, or the big mode preset
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        if name == "big" {
+            return Ok(BoardSize::BIG);
+        }
+
+        let invalid = || format!("Invalid board size '{}'", name);
+
+        let (width, height) = name.split_once('x').ok_or_else(invalid)?;
+
+        Ok(BoardSize {
+            width: width.parse().map_err(|_| invalid())?,
+            visible_height: height.parse().map_err(|_| invalid())?,
+            ..BoardSize::default()
+        })
+    }
+}
+
+impl fmt::Display for BoardSize {
+    /// Writes the size as read back by from_str
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if *self == BoardSize::BIG {
+            return write!(f, "big");
+        }
+
+        write!(f, "{}x{}", self.width, self.visible_height)
+    }
+}
+
+/// Represents what fills a position of the board
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Cell {
+    #[default]
+    Empty,
+    /// A cell of a tetromino of the given kind that locked there
+    Filled(PieceKind),
+}
+
+/// Represents the lines cleared by a tetromino that locked
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineClear {
+    pub rows: Vec<usize>, // The full rows, from top to bottom, as they were before being cleared
+}
+
+impl LineClear {
+    /// Returns the number of lines that were cleared
+    pub fn count(&self) -> u32 {
+        self.rows.len() as u32
+    }
+}
+
+/// Represents the playfield and the tetrominoes locked into it
+///
+/// Columns 0 and width + 1 are the walls and the row below the last one is the floor, so the
+/// playfield spans columns 1 to width. Only the playfield is stored, the first rows being the
+/// hidden ones
+pub struct Board {
+    size: BoardSize,
+    cells: Vec<Vec<Cell>>,
+}
+
+impl Board {
+    /// Creates an empty board of the given size
+    pub fn new(size: BoardSize) -> Board {
+        Board {
+            size,
+            cells: vec![vec![Cell::Empty; size.width]; size.height()],
+        }
+    }
+
+    /// Returns the dimensions of the board
+    pub fn size(&self) -> BoardSize {
+        self.size
+    }
+
+    /// Returns the number of columns of the playfield
+    pub fn width(&self) -> usize {
+        self.size.width
+    }
+
+    /// Returns the number of rows of the board, counting the hidden ones
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns the number of rows above the visible field
+    pub fn hidden_height(&self) -> usize {
+        self.size.hidden_height
+    }
+
+    /// Returns the cell at the given position, the walls and the floor being empty
+    pub fn cell(&self, x: usize, y: usize) -> Cell {
+        let column = x.checked_sub(1);
+
+        self.cells
+            .get(y)
+            .and_then(|row| row.get(column?))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Checks if the given position is filled, or is part of the walls or the floor
+    ///
+    /// The rows above the top of the board are open, so tetrominoes can stick out of it
+    pub fn is_occupied(&self, x: i32, y: i32) -> bool {
+        if x < 1 || x > self.width() as i32 || y >= self.height() as i32 {
+            return true;
+        }
+
+        y >= 0 && self.cells[y as usize][x as usize - 1] != Cell::Empty
+    }
+
+    /// Checks if every cell of the given row is filled
+    pub fn row_full(&self, y: usize) -> bool {
+        self.cells[y].iter().all(|&cell| cell != Cell::Empty)
+    }
+
+    /// Checks if no cell of the board is filled
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().flatten().all(|&cell| cell == Cell::Empty)
+    }
+
+    /// Returns how many rows the stack of the given column rises above the floor, 0 for the
+    /// walls and the columns past them
+    pub fn height_of_column(&self, x: usize) -> usize {
+        let Some(column) = x.checked_sub(1).filter(|&column| column < self.width()) else {
+            return 0;
+        };
+
+        self.cells
+            .iter()
+            .position(|row| row[column] != Cell::Empty)
+            .map_or(0, |top| self.height() - top)
+    }
+
+    /// Returns the number of empty cells that have a filled cell somewhere above them
+    pub fn holes(&self) -> usize {
+        (0..self.width())
+            .map(|x| {
+                self.cells
+                    .iter()
+                    .skip_while(|row| row[x] == Cell::Empty)
+                    .filter(|row| row[x] == Cell::Empty)
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Fills the cells of a tetromino that locked with its kind
+    pub fn place(&mut self, tetromino: &Tetromino) {
+        for (x, y) in tetromino.cells() {
+            self.cells[y as usize][x as usize - 1] = Cell::Filled(tetromino.kind);
+        }
+    }
+
+    /// Clears the full rows of the board
+    ///
+    /// The full rows are collected first, then removed all at once while the remaining rows keep
+    /// their order, so every row moves exactly once no matter how many lines were cleared or how
+    /// far apart they were
+    ///
+    /// # Returns
+    ///
+    /// The rows that were cleared
+    pub fn clear_rows(&mut self) -> LineClear {
+        let rows: Vec<usize> = (0..self.height()).filter(|&y| self.row_full(y)).collect();
+
+        self.cells.retain(|row| row.contains(&Cell::Empty));
+
+        // The rows above fall down as the full rows are removed, leaving empty rows at the top
+        for _ in &rows {
+            self.cells.insert(0, vec![Cell::Empty; self.size.width]);
+        }
+
+        LineClear { rows }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the cell marking a row that is not full, different for every row of the board
+    fn marker(y: usize) -> (usize, Cell) {
+        (
+            y % 10,
+            Cell::Filled(PieceKind::ALL[y % PieceKind::ALL.len()]),
+        )
+    }
+
+    #[test]
+    fn height_of_column_counts_from_the_floor_to_the_top_of_the_stack() {
+        let mut board = Board::new(BoardSize::default());
+        let bottom = board.height() - 1;
+
+        // A cell in the second column three rows up, with nothing below it
+        board.cells[bottom - 2][1] = Cell::Filled(PieceKind::T);
+
+        assert_eq!(board.height_of_column(1), 0);
+        assert_eq!(board.height_of_column(2), 3);
+        assert_eq!(board.height_of_column(0), 0);
+        assert_eq!(board.height_of_column(board.width() + 1), 0);
+    }
+
+    #[test]
+    fn holes_are_empty_cells_under_the_stack() {
+        let mut board = Board::new(BoardSize::default());
+        let bottom = board.height() - 1;
+
+        assert_eq!(board.holes(), 0);
+
+        // Two empty cells under the second column, and none under a column filled to the floor
+        board.cells[bottom - 2][1] = Cell::Filled(PieceKind::T);
+        board.cells[bottom][0] = Cell::Filled(PieceKind::O);
+        board.cells[bottom - 1][0] = Cell::Filled(PieceKind::O);
+
+        assert_eq!(board.holes(), 2);
+    }
+
+    #[test]
+    fn split_and_double_clears_move_every_row_once() {
+        let mut board = Board::new(BoardSize::default());
+        let full_rows = [3, 5, board.height() - 2, board.height() - 1];
+
+        for y in 0..board.height() {
+            if full_rows.contains(&y) {
+                board.cells[y] = vec![Cell::Filled(PieceKind::I); board.width()];
+            } else {
+                let (x, cell) = marker(y);
+                board.cells[y][x] = cell;
+            }
+        }
+
+        let line_clear = board.clear_rows();
+
+        assert_eq!(line_clear.rows, full_rows);
+
+        // The rows left keep their order and fall by the number of full rows below them
+        let kept = (0..board.height()).filter(|y| !full_rows.contains(y));
+        let mut expected = vec![vec![Cell::Empty; board.width()]; full_rows.len()];
+        expected.extend(kept.map(|y| {
+            let (x, cell) = marker(y);
+            let mut row = vec![Cell::Empty; board.width()];
+            row[x] = cell;
+            row
+        }));
+
+        assert_eq!(board.cells, expected);
+    }
+}
@@ -1,112 +1,424 @@
 extern crate termion;
 use std::io::Write;
+use std::time::Duration;
 use termion::clear;
 
-const WIDTH: usize = 12; // 2 more to account for the borders
-const HEIGHT: usize = 40;
+use crate::board::Cell;
+use crate::engine::{Engine, SPLIT_LINES};
+use crate::gravity::ONE_G;
+use crate::keymap::{Action, Keymap};
+use crate::records::HighScore;
+use crate::tetromino::characters::{BLOCK, GHOST};
+use crate::tetromino::kind::PieceKind;
+use crate::timing::FrameStats;
+use crate::{EndReason, GameMode};
 
-use crate::move_to_built;
-use crate::Tetromino;
-use crate::TetrominoCharacter;
+const BOARD_TOP: u16 = 3; // Terminal row the first visible row of the playfield is drawn on
+const PANEL_GAP: u16 = 3; // Columns between the right wall and the side panel
+const MENU_TOP: u16 = 4; // Terminal row the first entry of a menu is drawn on
 
-/// Adds the scrren elements to the screen array
+/// Draws the playfield with the built tetrominoes and the current tetromino, and the side panel
+/// next to it
 ///
 /// # Arguments
 ///
-/// * `screen` - An array representing the screen instelf
-pub fn create_screen(screen: &mut [[&str; WIDTH]; HEIGHT]) {
-    for i in 0..HEIGHT {
-        screen[i][0] = "<!";
-        for j in 1..WIDTH - 1 {
-            screen[i][j] = " . ";
+/// * `engine` - The game being played
+/// * `stdout` - The terminal the screen is drawn on
+/// * `ghost` - Whether to draw the ghost tetromino where the current tetromino would land
+/// * `stats` - The frame timing statistics to show above the playfield, if any
+pub fn display_screen(
+    engine: &Engine,
+    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
+    ghost: bool,
+    stats: Option<&FrameStats>,
+) {
+    let current_tetromino = &engine.current_tetromino;
+    let drop_distance = current_tetromino.drop_distance(&engine.board);
+
+    writeln!(stdout, "{}{}", clear::All, termion::cursor::Hide).unwrap();
+
+    display_hud(engine, stdout);
+
+    match stats {
+        Some(stats) => write!(
+            stdout,
+            "{} FPS: {:.1}   Late: {}   Dropped: {}   Render: {:.2} ms\n\r",
+            termion::cursor::Goto(12, 2),
+            stats.frame_rate(),
+            stats.late_frames,
+            stats.dropped_frames,
+            stats.average_render().as_secs_f64() * 1000.0
+        )
+        .unwrap(),
+        None => write!(stdout, "\n\r").unwrap(),
+    }
+
+    let board = &engine.board;
+    let scale = board.size().scale;
+
+    // The hidden rows above the visible field are not drawn, and every row of the board takes
+    // as many lines as its cells are scaled by
+    for i in (board.hidden_height()..board.height()).flat_map(|i| std::iter::repeat_n(i, scale)) {
+        write!(stdout, "<!").unwrap();
+
+        for j in 1..=board.width() {
+            let in_tetromino = current_tetromino
+                .cells()
+                .any(|(x, y)| x == j as i32 && y == i as i32);
+            let in_ghost = ghost
+                && current_tetromino
+                    .cells()
+                    .any(|(x, y)| x == j as i32 && y + drop_distance == i as i32);
+
+            if let Cell::Filled(_) = board.cell(j, i) {
+                write!(stdout, "{}", BLOCK.repeat(scale)).unwrap();
+            } else if in_tetromino {
+                write!(stdout, "{}", BLOCK.repeat(scale)).unwrap();
+            } else if in_ghost {
+                write!(
+                    stdout,
+                    "{}{}{}",
+                    termion::style::Faint,
+                    GHOST.repeat(scale),
+                    termion::style::Reset
+                )
+                .unwrap();
+            } else {
+                write!(stdout, "{}", " . ".repeat(scale)).unwrap();
+            }
         }
-        screen[i][WIDTH - 1] = "!>";
+
+        write!(stdout, "!>\n\r").unwrap();
     }
+
+    display_hold(stdout, engine);
+    display_queue(stdout, engine);
+
+    stdout.flush().unwrap();
 }
 
-pub fn display_screen(
-    screen: &[[&str; WIDTH]; HEIGHT],
-    current_tetromino: &mut Tetromino,
+/// Returns the terminal column of the side panel, right of the playfield
+fn panel_column(engine: &Engine) -> u16 {
+    let size = engine.board.size();
+
+    // Both walls take 2 columns and every cell 3 columns per scale
+    (2 + 3 * size.width * size.scale + 2) as u16 + PANEL_GAP
+}
+
+/// Draws the hold box, dimming the held tetromino while it cannot be swapped back in
+fn display_hold(stdout: &mut termion::raw::RawTerminal<std::io::Stdout>, engine: &Engine) {
+    write!(
+        stdout,
+        "{}HOLD",
+        termion::cursor::Goto(panel_column(engine), BOARD_TOP)
+    )
+    .unwrap();
+
+    if let Some(kind) = engine.hold {
+        if engine.hold_used {
+            write!(stdout, "{}", termion::style::Faint).unwrap();
+        }
+
+        draw_piece(stdout, kind, panel_column(engine), BOARD_TOP + 2);
+
+        write!(stdout, "{}", termion::style::Reset).unwrap();
+    }
+}
+
+/// Draws the pieces coming after the current tetromino below the hold box
+fn display_queue(stdout: &mut termion::raw::RawTerminal<std::io::Stdout>, engine: &Engine) {
+    if engine.next_pieces().next().is_none() {
+        return;
+    }
+
+    write!(
+        stdout,
+        "{}NEXT",
+        termion::cursor::Goto(panel_column(engine), BOARD_TOP + 5)
+    )
+    .unwrap();
+
+    for (i, kind) in engine.next_pieces().enumerate() {
+        draw_piece(
+            stdout,
+            kind,
+            panel_column(engine),
+            BOARD_TOP + 7 + 3 * i as u16,
+        );
+    }
+}
+
+/// Draws a piece in its spawn state with the top of the piece at the given terminal position
+fn draw_piece(
     stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
-    built_tetroinoes: &mut [[TetrominoCharacter; WIDTH]; HEIGHT],
+    kind: PieceKind,
+    column: u16,
+    row: u16,
+) {
+    let cells = kind.cells(0);
+    let top = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+
+    for &(x, y) in cells {
+        write!(
+            stdout,
+            "{}{}",
+            termion::cursor::Goto(column + 3 * x as u16, row + (y - top) as u16),
+            BLOCK
+        )
+        .unwrap();
+    }
+}
+
+/// Draws the line above the playfield, which shows the score in a marathon and the timer in a
+/// sprint
+///
+/// It can be drawn on its own to keep the timer running between redraws of the screen
+pub fn display_hud(engine: &Engine, stdout: &mut termion::raw::RawTerminal<std::io::Stdout>) {
+    write!(
+        stdout,
+        "{}{}",
+        termion::cursor::Goto(12, 0),
+        clear::CurrentLine
+    )
+    .unwrap();
+
+    match engine.mode() {
+        GameMode::Marathon => write!(
+            stdout,
+            " Score: {}   Level: {}   Gravity: {:.3}G",
+            engine.score,
+            engine.level,
+            engine.gravity() as f64 / ONE_G as f64
+        ),
+        GameMode::Sprint(goal) => write!(
+            stdout,
+            " Lines: {}/{}   Time: {}   PPS: {:.2}",
+            engine.lines.min(goal),
+            goal,
+            precise_clock(engine.duration()),
+            engine.pieces_per_second()
+        ),
+    }
+    .unwrap();
+
+    writeln!(stdout).unwrap();
+}
+
+/// Shows the pause screen, hiding the board until the game is resumed
+///
+/// # Arguments
+///
+/// * `stdout` - The terminal the screen is drawn on
+/// * `keymap` - The key bindings shown to resume, restart or go back to the menu
+pub fn display_pause(stdout: &mut termion::raw::RawTerminal<std::io::Stdout>, keymap: &Keymap) {
+    writeln!(stdout, "{}{}", clear::All, termion::cursor::Hide).unwrap();
+
+    write!(stdout, "{}PAUSED", termion::cursor::Goto(12, 2)).unwrap();
+    write!(
+        stdout,
+        "{}{}: resume   {}: restart   {}: menu",
+        termion::cursor::Goto(12, 4),
+        keymap.describe(Action::Pause),
+        keymap.describe(Action::Restart),
+        keymap.describe(Action::Quit)
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+}
+
+/// Shows the game over screen with the final results of the game
+///
+/// # Arguments
+///
+/// * `stdout` - The terminal the screen is drawn on
+/// * `keymap` - The key bindings shown to restart or go back to the menu
+/// * `score` - The final score of the game
+/// * `lines` - The number of lines cleared during the game
+/// * `duration` - How long the game lasted
+pub fn display_game_over(
+    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
+    keymap: &Keymap,
     score: u32,
+    lines: u32,
+    duration: Duration,
+) {
+    writeln!(stdout, "{}{}", clear::All, termion::cursor::Hide).unwrap();
+
+    write!(stdout, "{}GAME OVER", termion::cursor::Goto(12, 2)).unwrap();
+    write!(stdout, "{}Score: {}", termion::cursor::Goto(12, 4), score).unwrap();
+    write!(stdout, "{}Lines: {}", termion::cursor::Goto(12, 5), lines).unwrap();
+    write!(
+        stdout,
+        "{}Time:  {}",
+        termion::cursor::Goto(12, 6),
+        clock(duration)
+    )
+    .unwrap();
+    write!(
+        stdout,
+        "{}{}: restart   {}: menu",
+        termion::cursor::Goto(12, 8),
+        keymap.describe(Action::Restart),
+        keymap.describe(Action::Quit)
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+}
+
+/// Shows the results of a sprint, comparing the time of every split with the personal best
+///
+/// # Arguments
+///
+/// * `stdout` - The terminal the screen is drawn on
+/// * `keymap` - The key bindings shown to restart or go back to the menu
+/// * `engine` - The sprint that came to an end
+/// * `best` - The fastest sprint of the same length finished before this one, if any
+pub fn display_sprint_results(
+    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
+    keymap: &Keymap,
+    engine: &Engine,
+    best: Option<&HighScore>,
 ) {
+    let finished = engine.game_over == Some(EndReason::Finished);
+    let duration = engine.duration();
+
     writeln!(stdout, "{}{}", clear::All, termion::cursor::Hide).unwrap();
 
-    writeln!(stdout, "{} Score: {}", termion::cursor::Goto(12, 0), score).unwrap();
+    let title = match best {
+        _ if !finished => "GAME OVER",
+        Some(best) if best.duration <= duration => "FINISHED",
+        _ => "NEW PERSONAL BEST",
+    };
+    write!(stdout, "{}{}", termion::cursor::Goto(12, 2), title).unwrap();
 
-    write!(stdout, "\n\r").unwrap();
+    write!(
+        stdout,
+        "{}Time:   {}",
+        termion::cursor::Goto(12, 4),
+        precise_clock(duration)
+    )
+    .unwrap();
 
-    for i in 0..HEIGHT {
-        let mut j = 0;
-        while j < WIDTH {
-            let mut found_tetromino = false;
+    if let Some(best) = best.filter(|_| finished) {
+        write!(
+            stdout,
+            "   {} (best {})",
+            difference(duration, best.duration),
+            precise_clock(best.duration)
+        )
+        .unwrap();
+    }
 
-            if built_tetroinoes[i][j].x == j as i32
-                && built_tetroinoes[i][j].y == i as i32
-                && !built_tetroinoes[i][j].value.is_empty()
-            {
-                write!(stdout, "{}", built_tetroinoes[i][j].value).unwrap();
-                j += 1;
-                continue;
-            }
+    write!(
+        stdout,
+        "{}Lines:  {}   Pieces: {}   PPS: {:.2}",
+        termion::cursor::Goto(12, 5),
+        engine.lines,
+        engine.pieces,
+        engine.pieces_per_second()
+    )
+    .unwrap();
 
-            let skip_distance_first = (current_tetromino.first_line.characters.len()) as usize;
-            let skip_distance_second = (current_tetromino.second_line.characters.len()) as usize;
-
-            let skip_distance_third = (current_tetromino.third_line.characters.len()) as usize;
-
-            let skip_distance_fourth = (current_tetromino.fourth_line.characters.len()) as usize;
-
-            if current_tetromino.first_line.x as usize == j
-                && current_tetromino.first_line.y as usize == i
-            {
-                write!(stdout, "{}", current_tetromino.first_line).unwrap();
-                stdout.flush().unwrap();
-                j += skip_distance_first;
-                found_tetromino = true;
-                if current_tetromino.second_line.to_string().is_empty() {
-                    move_to_built(current_tetromino, built_tetroinoes);
-                }
-            } else if current_tetromino.second_line.x as usize == j
-                && current_tetromino.second_line.y as usize == i
-                && !current_tetromino.second_line.to_string().is_empty()
-            {
-                found_tetromino = true;
-                j += skip_distance_second;
-                write!(stdout, "{}", current_tetromino.second_line).unwrap();
-                stdout.flush().unwrap();
-                if current_tetromino.third_line.to_string().is_empty() {
-                    move_to_built(current_tetromino, built_tetroinoes);
-                }
-            } else if current_tetromino.third_line.x as usize == j
-                && current_tetromino.third_line.y as usize == i
-                && !current_tetromino.third_line.to_string().is_empty()
-            {
-                found_tetromino = true;
-                j += skip_distance_third;
-                write!(stdout, "{}", current_tetromino.third_line).unwrap();
-                stdout.flush().unwrap();
-                if current_tetromino.fourth_line.to_string().is_empty() {
-                    move_to_built(current_tetromino, built_tetroinoes);
-                }
-            } else if current_tetromino.shape_type == 1
-                && current_tetromino.fourth_line.x as usize == j
-                && current_tetromino.fourth_line.y as usize == i
-                && !current_tetromino.fourth_line.to_string().is_empty()
-            {
-                found_tetromino = true;
-                j += skip_distance_fourth;
-                write!(stdout, "{}", current_tetromino.fourth_line).unwrap();
-                stdout.flush().unwrap();
-                move_to_built(current_tetromino, built_tetroinoes);
-            }
+    let mut row = 7;
 
-            if !found_tetromino {
-                write!(stdout, "{}", screen[i][j]).unwrap();
-                j += 1;
-            }
+    for (index, &split) in engine.splits.iter().enumerate() {
+        write!(
+            stdout,
+            "{}{:>4} lines   {}",
+            termion::cursor::Goto(12, row),
+            (index as u32 + 1) * SPLIT_LINES,
+            precise_clock(split)
+        )
+        .unwrap();
+
+        if let Some(&best_split) = best.and_then(|best| best.splits.get(index)) {
+            write!(stdout, "   {}", difference(split, best_split)).unwrap();
         }
-        write!(stdout, "\n\r").unwrap();
+
+        row += 1;
     }
+
+    write!(
+        stdout,
+        "{}{}: restart   {}: menu",
+        termion::cursor::Goto(12, row + 1),
+        keymap.describe(Action::Restart),
+        keymap.describe(Action::Quit)
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+}
+
+/// Writes a duration as minutes and seconds
+pub fn clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Writes a duration as minutes, seconds and milliseconds
+pub fn precise_clock(duration: Duration) -> String {
+    format!("{}.{:03}", clock(duration), duration.subsec_millis())
+}
+
+/// Writes how much faster or slower a time is than another, as seconds and milliseconds
+fn difference(time: Duration, best: Duration) -> String {
+    let (sign, difference) = match time.checked_sub(best) {
+        Some(difference) => ('+', difference),
+        None => ('-', best - time),
+    };
+
+    format!(
+        "{}{}.{:03}",
+        sign,
+        difference.as_secs(),
+        difference.subsec_millis()
+    )
+}
+
+/// Shows a screen of the menu, a title above a list of entries
+///
+/// # Arguments
+///
+/// * `stdout` - The terminal the screen is drawn on
+/// * `title` - The title of the screen
+/// * `entries` - The entries of the list, one per line
+/// * `selected` - The entry the player is on, if the entries can be chosen
+/// * `hint` - The keys the screen responds to, shown below the list
+pub fn display_menu(
+    stdout: &mut termion::raw::RawTerminal<std::io::Stdout>,
+    title: &str,
+    entries: &[String],
+    selected: Option<usize>,
+    hint: &str,
+) {
+    writeln!(stdout, "{}{}", clear::All, termion::cursor::Hide).unwrap();
+
+    write!(stdout, "{}{}", termion::cursor::Goto(12, 2), title).unwrap();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let marker = if selected == Some(index) { ">" } else { " " };
+
+        write!(
+            stdout,
+            "{}{} {}",
+            termion::cursor::Goto(12, MENU_TOP + index as u16),
+            marker,
+            entry
+        )
+        .unwrap();
+    }
+
+    write!(
+        stdout,
+        "{}{}",
+        termion::cursor::Goto(12, MENU_TOP + entries.len() as u16 + 1),
+        hint
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
 }
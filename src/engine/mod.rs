@@ -0,0 +1,707 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::board::{Board, LineClear};
+use crate::gravity::{MAX_GRAVITY, ONE_G};
+use crate::randomizer::{self, Randomizer};
+use crate::ruleset::{LockReset, SpawnRule};
+use crate::scoring::{self, LockScore, Scoring};
+use crate::tetromino;
+use crate::tetromino::kind::PieceKind;
+use crate::tetromino::tetromino::{PieceState, Tetromino};
+use crate::{EndReason, GameConfig, GameMode};
+
+/// The number of frames the engine simulates per second of game time
+pub const FRAME_RATE: u32 = 60;
+
+/// The largest number of pieces the queue can preview
+pub const MAX_PREVIEW: usize = 6;
+
+/// The number of lines between two splits of the timer
+pub const SPLIT_LINES: u32 = 10;
+
+const MAX_LOCK_RESETS: u32 = 15; // Moves that restart the lock delay under the move reset rule
+
+/// Represents an action of the player on the current tetromino
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateCounterClockwise,
+    Rotate180,
+    Hold,
+}
+
+/// Represents a side key held down, repeating its move once DAS has charged
+struct AutoShift {
+    input: Input, // MoveLeft or MoveRight
+    frames: u32,  // Frames the key has been held for
+}
+
+/// Represents something that happened in the game while applying an input or a tick
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The current tetromino was placed on the board
+    PieceLocked,
+    /// Lines were cleared by the tetromino that just locked
+    LinesCleared(LineClear),
+    /// The tetromino that just locked was scored
+    Scored(LockScore),
+    /// The level went up to the given level
+    LevelUp(u32),
+    /// The game came to an end, further inputs and ticks are ignored
+    GameOver(EndReason),
+}
+
+/// Represents the state of a game, independent of any terminal or clock
+///
+/// The engine only moves forward when it is given inputs or told how many frames have passed,
+/// so the same seed and the same inputs always play out the same game
+pub struct Engine {
+    pub current_tetromino: Tetromino,
+    // When the tetrominoes are locked, they are placed on the board
+    //
+    // The board is used to check for collisions with the tetrominoes that have already been built.
+    // Also, the board is used to render the tetrominoes that have already been built and to handle
+    // the scoring system.
+    pub board: Board,
+    pub hold: Option<PieceKind>,
+    pub hold_used: bool, // The current tetromino came out of hold, so it cannot be held again
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+    pub pieces: u32,           // Tetrominoes locked since the game started
+    pub splits: Vec<Duration>, // Time every tenth line was cleared at
+    pub frames: u64,
+    pub game_over: Option<EndReason>,
+    pub seed: u64,
+    config: GameConfig,
+    randomizer: Box<dyn Randomizer>,
+    scoring: Scoring,
+    queue: VecDeque<PieceKind>, // The pieces dealt by the randomizer that are shown in the preview
+    gravity_counter: u32,       // Fraction of a row the tetromino has fallen, in units of 1/65536
+    lock_counter: u32, // Frames the current tetromino has spent on the stack since its lock delay restarted
+    lock_resets: u32, // Moves that restarted the lock delay since the tetromino reached its lowest row
+    shift: Option<AutoShift>, // The side key that moves the tetromino, the last one pressed
+    left_held: bool,
+    right_held: bool,
+    soft_drop_held: bool,
+    shift_cut: u32, // Frames left before auto shift can move the tetromino again, after DCD
+}
+
+impl Engine {
+    /// Creates a new game with the first tetromino already spawned
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The settings of the game
+    /// * `seed` - The seed of the randomizer, the same seed always deals the same tetrominoes
+    pub fn new(config: GameConfig, seed: u64) -> Engine {
+        let mut randomizer = randomizer::create_randomizer(config.ruleset.randomizer, seed);
+        let first_piece = randomizer.next_piece();
+        let queue = (0..config.ruleset.preview_length.min(MAX_PREVIEW))
+            .map(|_| randomizer.next_piece())
+            .collect();
+
+        let board = Board::new(config.board);
+
+        let mut engine = Engine {
+            current_tetromino: tetromino::create_tetronimo(
+                first_piece,
+                config.ruleset.spawn,
+                &board,
+            ),
+            board,
+            hold: None,
+            hold_used: false,
+            score: 0,
+            lines: 0,
+            level: config.ruleset.start_level,
+            pieces: 0,
+            splits: Vec::new(),
+            frames: 0,
+            game_over: None,
+            seed,
+            config,
+            randomizer,
+            scoring: Scoring::new(config.ruleset.scoring),
+            queue,
+            gravity_counter: 0,
+            lock_counter: 0,
+            lock_resets: 0,
+            shift: None,
+            left_held: false,
+            right_held: false,
+            soft_drop_held: false,
+            shift_cut: 0,
+        };
+
+        engine.enter_field();
+
+        engine
+    }
+
+    /// Presses the key of an input, applying it once
+    ///
+    /// Side keys keep moving the tetromino once held for the DAS delay, every ARR frames, and
+    /// the soft drop key multiplies gravity by the soft drop factor, until they are released
+    ///
+    /// # Returns
+    ///
+    /// The events that happened because of the input
+    pub fn press(&mut self, input: Input) -> Vec<Event> {
+        match input {
+            Input::MoveLeft => self.left_held = true,
+            Input::MoveRight => self.right_held = true,
+            Input::SoftDrop => self.soft_drop_held = true,
+            _ => {}
+        }
+
+        if let Input::MoveLeft | Input::MoveRight = input {
+            self.shift = Some(AutoShift { input, frames: 0 });
+        }
+
+        self.apply(input)
+    }
+
+    /// Releases the key of an input pressed before
+    ///
+    /// Releasing a side key while the other one is still held hands the auto shift over to it,
+    /// charging DAS again
+    pub fn release(&mut self, input: Input) {
+        let other = match input {
+            Input::MoveLeft => {
+                self.left_held = false;
+                self.right_held.then_some(Input::MoveRight)
+            }
+            Input::MoveRight => {
+                self.right_held = false;
+                self.left_held.then_some(Input::MoveLeft)
+            }
+            Input::SoftDrop => {
+                self.soft_drop_held = false;
+                return;
+            }
+            _ => return,
+        };
+
+        if self
+            .shift
+            .as_ref()
+            .is_some_and(|shift| shift.input == input)
+        {
+            self.shift = other.map(|input| AutoShift { input, frames: 0 });
+        }
+    }
+
+    /// Applies an input of the player to the current tetromino
+    ///
+    /// # Returns
+    ///
+    /// The events that happened because of the input
+    pub fn apply(&mut self, input: Input) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        if self.game_over.is_some() {
+            return events;
+        }
+
+        let kicks_180 = self.config.ruleset.kicks_180;
+        let tetromino = &mut self.current_tetromino;
+        let lowest_row = tetromino.lowest_row;
+
+        let moved = match input {
+            Input::MoveLeft => tetromino.move_tetromino(-1, 0, &self.board),
+            Input::MoveRight => tetromino.move_tetromino(1, 0, &self.board),
+            Input::SoftDrop => tetromino.move_tetromino(0, 1, &self.board),
+            Input::HardDrop => {
+                let distance = tetromino.drop_distance(&self.board);
+                tetromino.y += distance;
+
+                // Dropping after a rotation means the tetromino did not spin into place
+                if distance > 0 {
+                    tetromino.last_kick = None;
+                }
+                tetromino.state = PieceState::Locked;
+                false
+            }
+            Input::RotateClockwise => tetromino.rotate(90, kicks_180, &self.board).is_some(),
+            Input::RotateCounterClockwise => {
+                tetromino.rotate(-90, kicks_180, &self.board).is_some()
+            }
+            Input::Rotate180 => tetromino.rotate(180, kicks_180, &self.board).is_some(),
+            Input::Hold => {
+                self.hold_tetromino(&mut events);
+                return events;
+            }
+        };
+
+        if moved {
+            self.reset_lock_delay(lowest_row);
+
+            if let Input::RotateClockwise | Input::RotateCounterClockwise | Input::Rotate180 = input
+            {
+                self.shift_cut = frames_of(self.config.handling.dcd);
+            }
+        }
+
+        self.update_piece_state();
+        self.lock_if_locked(&mut events);
+
+        events
+    }
+
+    /// Advances the game by the given number of frames, applying gravity to the current tetromino
+    /// and running down its lock delay while it rests on the stack
+    ///
+    /// # Returns
+    ///
+    /// The events that happened during those frames
+    pub fn tick(&mut self, frames: u32) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        for _ in 0..frames {
+            if self.game_over.is_some() {
+                break;
+            }
+
+            self.frames += 1;
+
+            events.extend(self.auto_shift());
+
+            if self.game_over.is_some() {
+                break;
+            }
+
+            // 20G drops the tetromino onto the stack on every frame, however tall the board is
+            self.gravity_counter = match self.fall_speed() {
+                speed if speed >= MAX_GRAVITY => ONE_G.saturating_mul(self.board.height() as u32),
+                speed => self.gravity_counter + speed,
+            };
+
+            // Strong gravity moves the tetromino several rows in a single frame
+            while self.gravity_counter >= ONE_G {
+                self.gravity_counter -= ONE_G;
+
+                let lowest_row = self.current_tetromino.lowest_row;
+                if !self.current_tetromino.move_tetromino(0, 1, &self.board) {
+                    self.gravity_counter = 0;
+                    break;
+                }
+
+                self.reset_lock_delay(lowest_row);
+            }
+
+            self.update_piece_state();
+
+            if self.current_tetromino.state != PieceState::Falling {
+                self.lock_counter += 1;
+
+                if self.lock_counter >= self.lock_delay_frames() {
+                    self.current_tetromino.state = PieceState::Locked;
+                }
+            }
+
+            self.lock_if_locked(&mut events);
+        }
+
+        events
+    }
+
+    /// Iterates over the pieces coming after the current tetromino, in the order they will spawn
+    pub fn next_pieces(&self) -> impl Iterator<Item = PieceKind> + '_ {
+        self.queue.iter().copied()
+    }
+
+    /// Returns the gravity of the current level, in rows per frame in units of 1/65536
+    pub fn gravity(&self) -> u32 {
+        self.config.ruleset.gravity.gravity(self.level)
+    }
+
+    /// Returns the gravity the current tetromino falls with, sped up while soft dropping
+    fn fall_speed(&self) -> u32 {
+        if !self.soft_drop_held {
+            return self.gravity();
+        }
+
+        self.gravity()
+            .saturating_mul(self.config.handling.soft_drop_factor)
+            .min(MAX_GRAVITY)
+    }
+
+    /// Moves the current tetromino for the held side key, once DAS has charged and every ARR
+    /// frames after that
+    fn auto_shift(&mut self) -> Vec<Event> {
+        let das = frames_of(self.config.handling.das);
+        let arr = frames_of(self.config.handling.arr);
+
+        let Some(shift) = &mut self.shift else {
+            return Vec::new();
+        };
+
+        shift.frames += 1;
+        let (input, frames) = (shift.input, shift.frames);
+
+        if self.shift_cut > 0 {
+            self.shift_cut -= 1;
+            return Vec::new();
+        }
+
+        if frames < das {
+            return Vec::new();
+        }
+
+        if arr == 0 {
+            // An ARR of 0 moves the tetromino all the way to the wall on the same frame
+            let mut events = Vec::new();
+            let width = self.board.width();
+
+            for _ in 0..width {
+                events.extend(self.apply(input));
+            }
+
+            events
+        } else if (frames - das).is_multiple_of(arr) {
+            self.apply(input)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns how much game time has passed since the game started
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.frames * 1000 / FRAME_RATE as u64)
+    }
+
+    /// Returns the mode the game is played in
+    pub fn mode(&self) -> GameMode {
+        self.config.mode
+    }
+
+    /// Returns the tetrominoes locked per second of game time
+    pub fn pieces_per_second(&self) -> f64 {
+        self.pieces as f64 / self.duration().as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Returns the number of frames a landed tetromino waits before locking
+    fn lock_delay_frames(&self) -> u32 {
+        frames_of(self.config.ruleset.lock_delay)
+    }
+
+    /// Restarts the lock delay after the current tetromino moved or rotated, as far as the lock
+    /// reset rule allows
+    ///
+    /// # Arguments
+    ///
+    /// * `lowest_row` - The lowest row the tetromino had reached before it moved
+    fn reset_lock_delay(&mut self, lowest_row: i32) {
+        // Reaching a new lowest row always restarts the lock delay and gives back every reset
+        if self.current_tetromino.lowest_row > lowest_row {
+            self.lock_counter = 0;
+            self.lock_resets = 0;
+            return;
+        }
+
+        match self.config.ruleset.lock_reset {
+            LockReset::Infinite => self.lock_counter = 0,
+            LockReset::Move => {
+                // Moves in the air do not use up any reset
+                if self.current_tetromino.state == PieceState::Landed
+                    && self.lock_resets < MAX_LOCK_RESETS
+                {
+                    self.lock_resets += 1;
+                    self.lock_counter = 0;
+                }
+            }
+            LockReset::Step => {}
+        }
+    }
+
+    /// Updates whether the current tetromino is falling or resting on the stack, and whether
+    /// moving it can still restart its lock delay
+    fn update_piece_state(&mut self) {
+        let tetromino = &mut self.current_tetromino;
+
+        if tetromino.state == PieceState::Locked {
+            return;
+        }
+
+        let can_reset = match self.config.ruleset.lock_reset {
+            LockReset::Infinite => true,
+            LockReset::Move => self.lock_resets < MAX_LOCK_RESETS,
+            LockReset::Step => false,
+        };
+
+        tetromino.state = if tetromino.drop_distance(&self.board) > 0 {
+            PieceState::Falling
+        } else if can_reset {
+            PieceState::Landed
+        } else {
+            PieceState::Locking
+        };
+    }
+
+    /// Places the current tetromino on the board once it is locked, clears the complete
+    /// lines and spawns the next tetromino, ending the game if it tops out
+    fn lock_if_locked(&mut self, events: &mut Vec<Event>) {
+        if self.current_tetromino.state != PieceState::Locked {
+            return;
+        }
+
+        let t_spin = scoring::t_spin(&self.current_tetromino, &self.board);
+
+        self.board.place(&self.current_tetromino);
+        self.pieces += 1;
+        events.push(Event::PieceLocked);
+
+        let lock_out = locked_out(&self.current_tetromino, &self.board);
+
+        let line_clear = self.board.clear_rows();
+        let lines_cleared = line_clear.count();
+        let perfect_clear = lines_cleared > 0 && self.board.is_empty();
+
+        let lock_score = self
+            .scoring
+            .score_lock(lines_cleared, t_spin, perfect_clear, self.level);
+        if lock_score.points > 0 {
+            self.score += lock_score.points;
+            events.push(Event::Scored(lock_score));
+        }
+
+        if lines_cleared > 0 {
+            self.lines += lines_cleared;
+            events.push(Event::LinesCleared(line_clear));
+
+            let ruleset = &self.config.ruleset;
+            let level = ruleset.start_level + self.lines / ruleset.lines_per_level.max(1);
+            if level > self.level {
+                self.level = level;
+                events.push(Event::LevelUp(level));
+            }
+
+            while (self.splits.len() as u32 + 1) * SPLIT_LINES <= self.lines {
+                self.splits.push(self.duration());
+            }
+        }
+
+        // Reaching the goal wins the game, even with a tetromino that locked out
+        if self
+            .config
+            .mode
+            .line_goal()
+            .is_some_and(|goal| self.lines >= goal)
+        {
+            self.game_over = Some(EndReason::Finished);
+            events.push(Event::GameOver(EndReason::Finished));
+            return;
+        }
+
+        if lock_out {
+            self.game_over = Some(EndReason::LockOut);
+            events.push(Event::GameOver(EndReason::LockOut));
+            return;
+        }
+
+        self.hold_used = false;
+
+        let kind = self.next_piece();
+        self.spawn(kind, events);
+    }
+
+    /// Takes the first piece out of the queue, refilling it from the randomizer
+    fn next_piece(&mut self) -> PieceKind {
+        self.queue.push_back(self.randomizer.next_piece());
+        self.queue.pop_front().unwrap()
+    }
+
+    /// Puts the current tetromino in the hold box and brings in the tetromino that was held,
+    /// or the next one if the hold box was empty
+    fn hold_tetromino(&mut self, events: &mut Vec<Event>) {
+        if self.hold_used && !self.config.ruleset.infinite_hold {
+            return;
+        }
+
+        let kind = match self.hold.replace(self.current_tetromino.kind) {
+            Some(kind) => kind,
+            None => self.next_piece(),
+        };
+
+        self.hold_used = true;
+        self.spawn(kind, events);
+    }
+
+    /// Brings in a new tetromino of the given kind, ending the game if it blocks out
+    fn spawn(&mut self, kind: PieceKind, events: &mut Vec<Event>) {
+        self.current_tetromino =
+            tetromino::create_tetronimo(kind, self.config.ruleset.spawn, &self.board);
+        self.gravity_counter = 0;
+        self.lock_counter = 0;
+        self.lock_resets = 0;
+        self.shift_cut = frames_of(self.config.handling.dcd);
+
+        // A new tetromino that spawns on top of the stack has blocked out
+        if !tetromino::fits(&self.current_tetromino, &self.board) {
+            self.game_over = Some(EndReason::BlockOut);
+            events.push(Event::GameOver(EndReason::BlockOut));
+            return;
+        }
+
+        self.enter_field();
+    }
+
+    /// Drops a tetromino that spawned in the hidden rows one row into view when there is room
+    fn enter_field(&mut self) {
+        if self.config.ruleset.spawn != SpawnRule::Guideline {
+            return;
+        }
+
+        self.current_tetromino.move_tetromino(0, 1, &self.board);
+    }
+}
+
+/// Converts a number of milliseconds into the nearest number of frames
+///
+/// The product is taken in 64 bits, so no time given in milliseconds can overflow it
+pub fn frames_of(milliseconds: u32) -> u32 {
+    ((milliseconds as u64 * FRAME_RATE as u64 + 500) / 1000) as u32
+}
+
+/// Checks if all of the tetromino's cells are above the visible field (lock out)
+fn locked_out(tetromino: &Tetromino, board: &Board) -> bool {
+    tetromino
+        .cells()
+        .all(|(_, y)| y < board.hidden_height() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Cell;
+
+    // Inputs given in turn by the scripted player, each held for a few frames
+    const SCRIPT: [Input; 9] = [
+        Input::MoveLeft,
+        Input::RotateClockwise,
+        Input::SoftDrop,
+        Input::HardDrop,
+        Input::MoveRight,
+        Input::Hold,
+        Input::Rotate180,
+        Input::RotateCounterClockwise,
+        Input::HardDrop,
+    ];
+
+    /// Returns every cell of the board, row by row
+    fn cells(board: &Board) -> Vec<Cell> {
+        (0..board.height())
+            .flat_map(|y| (1..=board.width()).map(move |x| board.cell(x, y)))
+            .collect()
+    }
+
+    /// Plays the scripted inputs on a new game, returning the engine and every event
+    fn play_script(seed: u64) -> (Engine, Vec<Event>) {
+        let mut engine = Engine::new(GameConfig::default(), seed);
+        let mut events = Vec::new();
+
+        for step in 0..2000 {
+            let input = SCRIPT[step % SCRIPT.len()];
+
+            events.extend(engine.press(input));
+            events.extend(engine.tick(step as u32 % 7));
+            engine.release(input);
+            events.extend(engine.tick(3));
+        }
+
+        (engine, events)
+    }
+
+    /// Returns a new game where the current tetromino has been held, so the next one spawned on
+    /// the given board
+    fn spawn_on(fill: impl Fn(&mut Board)) -> (Engine, Vec<Event>) {
+        let mut engine = Engine::new(GameConfig::default(), 1);
+        fill(&mut engine.board);
+
+        let events = engine.press(Input::Hold);
+
+        (engine, events)
+    }
+
+    #[test]
+    fn same_seed_and_inputs_play_the_same_game() {
+        let (first, first_events) = play_script(3);
+        let (second, second_events) = play_script(3);
+
+        assert!(first.pieces > 10);
+        assert_eq!(cells(&first.board), cells(&second.board));
+        assert_eq!(first.score, second.score);
+        assert_eq!(first.lines, second.lines);
+        assert_eq!(first.frames, second.frames);
+        assert_eq!(first.game_over, second.game_over);
+        assert_eq!(first_events, second_events);
+    }
+
+    #[test]
+    fn max_gravity_drops_to_the_floor_of_a_tall_board() {
+        let mut config = GameConfig::default();
+        config.ruleset.start_level = 20;
+
+        let mut engine = Engine::new(config, 1);
+        assert_eq!(engine.gravity(), MAX_GRAVITY);
+        assert!(engine.current_tetromino.drop_distance(&engine.board) > 20);
+
+        engine.tick(1);
+
+        assert_eq!(engine.current_tetromino.drop_distance(&engine.board), 0);
+    }
+
+    #[test]
+    fn longest_handling_times_do_not_overflow() {
+        let mut config = GameConfig::default();
+        config.handling.das = u32::MAX;
+        config.handling.arr = u32::MAX;
+        config.handling.dcd = u32::MAX;
+        config.ruleset.lock_delay = u32::MAX;
+
+        let mut engine = Engine::new(config, 1);
+        engine.press(Input::MoveLeft);
+        engine.tick(10);
+
+        assert_eq!(frames_of(u32::MAX), 257_698_038);
+    }
+
+    #[test]
+    fn tetromino_spawning_on_the_stack_blocks_out() {
+        // The rows the tetrominoes spawn in are filled across the middle of the board
+        let (engine, events) = spawn_on(|board| {
+            let spawn_row = board.hidden_height() as i32 - 2;
+            board.place(&Tetromino::new(PieceKind::I, 4, spawn_row));
+            board.place(&Tetromino::new(PieceKind::I, 4, spawn_row - 1));
+        });
+
+        assert_eq!(engine.game_over, Some(EndReason::BlockOut));
+        assert_eq!(events, [Event::GameOver(EndReason::BlockOut)]);
+    }
+
+    #[test]
+    fn tetromino_locking_above_the_field_locks_out() {
+        // The first visible row is filled, except for one column so it is not cleared
+        let (mut engine, events) = spawn_on(|board| {
+            let row = board.hidden_height() as i32 - 1;
+            board.place(&Tetromino::new(PieceKind::I, 1, row));
+            board.place(&Tetromino::new(PieceKind::I, 6, row));
+        });
+
+        assert!(events.is_empty());
+
+        let events = engine.press(Input::HardDrop);
+
+        assert_eq!(engine.game_over, Some(EndReason::LockOut));
+        assert_eq!(
+            events,
+            [Event::PieceLocked, Event::GameOver(EndReason::LockOut)]
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::str::FromStr;
+
+/// Gravity is measured in 1/65536 of a row per frame, so one row per frame is 1G
+pub const ONE_G: u32 = 65536;
+
+/// The strongest gravity, where tetrominoes fall all the way down on every frame, however tall
+/// the board is
+pub const MAX_GRAVITY: u32 = 20 * ONE_G;
+
+const GUIDELINE_MAX_LEVEL: u32 = 20; // The formula reaches 20G by this level
+
+// Frames it takes for a tetromino to fall one row on each level of the NES version, level 29
+// and above falling one row every frame
+const NES_FRAMES_PER_ROW: [u32; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    1,
+];
+
+/// Represents how fast tetrominoes fall as the level goes up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GravityCurve {
+    /// The guideline formula, where a row takes (0.8 - (level - 1) * 0.007) ^ (level - 1)
+    /// seconds, reaching 20G at level 20
+    Guideline,
+    /// The frame table of the NES version
+    Nes,
+}
+
+impl GravityCurve {
+    /// Every gravity curve, in the order they are offered to the player
+    pub const ALL: [GravityCurve; 2] = [GravityCurve::Guideline, GravityCurve::Nes];
+
+    /// Returns the name used to select the gravity curve from the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            GravityCurve::Guideline => "guideline",
+            GravityCurve::Nes => "nes",
+        }
+    }
+
+    /// Returns the gravity of the given level
+    ///
+    /// # Returns
+    ///
+    /// The rows a tetromino falls per frame, in units of 1/65536 of a row
+    pub fn gravity(self, level: u32) -> u32 {
+        match self {
+            GravityCurve::Guideline => {
+                let level = level.clamp(1, GUIDELINE_MAX_LEVEL) as f64;
+                let seconds_per_row = (0.8 - (level - 1.0) * 0.007).powf(level - 1.0);
+                let rows_per_frame = 1.0 / (seconds_per_row * 60.0);
+
+                // Rounding up keeps a row from taking one frame longer than the formula says
+                (rows_per_frame * ONE_G as f64)
+                    .ceil()
+                    .min(MAX_GRAVITY as f64) as u32
+            }
+            GravityCurve::Nes => {
+                let frames = NES_FRAMES_PER_ROW[(level as usize).min(NES_FRAMES_PER_ROW.len() - 1)];
+
+                ONE_G.div_ceil(frames)
+            }
+        }
+    }
+}
+
+impl FromStr for GravityCurve {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        GravityCurve::ALL
+            .into_iter()
+            .find(|curve| curve.name() == name)
+            .ok_or_else(|| format!("Unknown gravity curve '{}'", name))
+    }
+}
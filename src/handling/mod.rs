@@ -0,0 +1,19 @@
+/// Represents how the game responds to keys held down, chosen by the player rather than the rules
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Handling {
+    pub das: u32, // Delayed Auto Shift, milliseconds a side key is held before it starts repeating
+    pub arr: u32, // Auto Repeat Rate, milliseconds between repeated moves, 0 moving to the wall at once
+    pub dcd: u32, // DAS Cut Delay, milliseconds repeating waits after a rotation or a new tetromino
+    pub soft_drop_factor: u32, // How many times faster than gravity the tetromino falls while soft dropping
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling {
+            das: 167,
+            arr: 33,
+            dcd: 0,
+            soft_drop_factor: 20,
+        }
+    }
+}
@@ -0,0 +1,768 @@
+use std::fmt;
+use std::io::{Bytes, Read, Write};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Kitty keyboard protocol, see https://sw.kovidgoyal.net/kitty/keyboard-protocol/
+// Flags 1 + 2 + 4 + 8: disambiguate escape codes, report press, repeat and release events,
+// report the character Shift types, and report every key as an escape code so letters are
+// released too
+const KITTY_ENABLE: &str = "\x1b[>15u";
+const KITTY_DISABLE: &str = "\x1b[<u";
+// Asks for the current keyboard flags, followed by the primary device attributes that every
+// terminal answers, so terminals without the protocol do not leave the query hanging
+const KITTY_QUERY: &str = "\x1b[?u\x1b[c";
+const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+// Without release events, a key is held once its byte comes again within this interval, as it
+// does at the terminal's key repeat rate, and it is released once the repeats stop. The first
+// repeat only comes after the terminal's much longer repeat delay, so tapping a key twice never
+// holds it
+const REPEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+// The escape key sends the byte that starts escape sequences, so an escape that nothing follows
+// within this time is the key itself
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(50);
+
+const MAX_FUNCTION_KEY: u8 = 35;
+
+// Kitty reports keys without a character, such as F13 to F35, with codes in the private use area
+const KITTY_PRIVATE_KEYS: std::ops::RangeInclusive<u32> = 0xe000..=0xf8ff;
+const KITTY_F13: u32 = 57376;
+
+/// Represents a key of the keyboard, without the modifiers held with it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    /// A key that types the given character, the space bar typing ' '
+    Char(char),
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    Backspace,
+    Tab,
+    Enter,
+    Escape,
+    /// The function key of the given number, from F1 to F35
+    F(u8),
+}
+
+impl FromStr for KeyCode {
+    type Err = String;
+
+    /// Reads the name of a key, a single character or the name of a special key
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let mut chars = name.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(character), None) if !character.is_control() => {
+                return Ok(KeyCode::Char(character));
+            }
+            _ => {}
+        }
+
+        let lowercase = name.to_lowercase();
+
+        if let Some(number) = lowercase.strip_prefix('f') {
+            return match number.parse() {
+                Ok(number @ 1..=MAX_FUNCTION_KEY) => Ok(KeyCode::F(number)),
+                _ => Err(format!("Unknown key '{}'", name)),
+            };
+        }
+
+        match lowercase.as_str() {
+            "space" => Ok(KeyCode::Char(' ')),
+            "left" => Ok(KeyCode::Left),
+            "right" => Ok(KeyCode::Right),
+            "up" => Ok(KeyCode::Up),
+            "down" => Ok(KeyCode::Down),
+            "home" => Ok(KeyCode::Home),
+            "end" => Ok(KeyCode::End),
+            "pageup" => Ok(KeyCode::PageUp),
+            "pagedown" => Ok(KeyCode::PageDown),
+            "insert" => Ok(KeyCode::Insert),
+            "delete" => Ok(KeyCode::Delete),
+            "backspace" => Ok(KeyCode::Backspace),
+            "tab" => Ok(KeyCode::Tab),
+            "enter" => Ok(KeyCode::Enter),
+            "escape" | "esc" => Ok(KeyCode::Escape),
+            _ => Err(format!("Unknown key '{}'", name)),
+        }
+    }
+}
+
+impl fmt::Display for KeyCode {
+    /// Writes the name of the key, as read back by from_str
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(character) => write!(f, "{}", character),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Escape => write!(f, "escape"),
+            KeyCode::F(number) => write!(f, "f{}", number),
+        }
+    }
+}
+
+/// Represents the modifier keys held while a key is pressed
+///
+/// Shift is only kept for keys that do not type a character, since it already changes the
+/// character the others type
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    /// Reads the modifiers of an escape sequence, written as 1 plus a bit for every modifier
+    fn from_parameter(parameter: &str) -> Modifiers {
+        let bits = parameter.parse::<u32>().unwrap_or(1).saturating_sub(1);
+
+        Modifiers {
+            shift: bits & 1 != 0,
+            alt: bits & 2 != 0,
+            ctrl: bits & 4 != 0,
+        }
+    }
+}
+
+/// Represents a key of the keyboard together with the modifiers held with it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl Key {
+    /// Creates a key pressed without modifiers
+    pub const fn new(code: KeyCode) -> Key {
+        Key {
+            code,
+            modifiers: Modifiers {
+                shift: false,
+                ctrl: false,
+                alt: false,
+            },
+        }
+    }
+
+    /// Creates a key pressed with the given modifiers, folding Shift into the character typed
+    fn with_modifiers(code: KeyCode, mut modifiers: Modifiers) -> Key {
+        if let KeyCode::Char(_) = code {
+            modifiers.shift = false;
+        }
+
+        Key { code, modifiers }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// Reads the name of a key, preceded by the modifiers held with it, such as ctrl+left
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Modifiers::default();
+        let mut rest = name;
+
+        // The key itself can be '+', so only the parts before the last one are modifiers
+        while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "shift" => modifiers.shift = true,
+                "ctrl" => modifiers.ctrl = true,
+                "alt" => modifiers.alt = true,
+                _ => break,
+            }
+
+            rest = key;
+        }
+
+        // Shift changes the character a key types, so it can only be named for letters
+        let code = match rest.parse()? {
+            KeyCode::Char(character) if modifiers.shift && character.is_alphabetic() => {
+                KeyCode::Char(character.to_uppercase().next().unwrap_or(character))
+            }
+            KeyCode::Char(character) if modifiers.shift && character != ' ' => {
+                return Err(format!(
+                    "Use the character typed with shift instead of '{}'",
+                    name
+                ));
+            }
+            code => code,
+        };
+
+        Ok(Key::with_modifiers(code, modifiers))
+    }
+}
+
+impl fmt::Display for Key {
+    /// Writes the name of the key, as read back by from_str
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.ctrl {
+            write!(f, "ctrl+")?;
+        }
+
+        if self.modifiers.alt {
+            write!(f, "alt+")?;
+        }
+
+        if self.modifiers.shift {
+            write!(f, "shift+")?;
+        }
+
+        write!(f, "{}", self.code)
+    }
+}
+
+/// Represents what happened to a key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyState {
+    Press,
+    /// The key is still held and the terminal repeated it
+    Repeat,
+    Release,
+}
+
+/// Represents a key of the keyboard changing state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub state: KeyState,
+    // The key as typed without Shift, which stays the same from its press to its release even
+    // when modifiers are pressed or released in between
+    pub base: KeyCode,
+}
+
+impl KeyEvent {
+    /// Creates the event of a key whose code does not depend on Shift
+    fn new(key: Key, state: KeyState) -> KeyEvent {
+        KeyEvent {
+            key,
+            state,
+            base: key.code,
+        }
+    }
+}
+
+/// Turns the bytes read from the terminal into key presses and releases
+///
+/// Terminals that support the kitty keyboard protocol report when keys are released. Other
+/// terminals only send a key again while it is held, so a key counts as held once the terminal
+/// repeats it, and as released once the repeats stop
+pub struct Keyboard {
+    kitty: bool,
+    sequence: Vec<u8>, // The escape sequence or multi-byte character being read
+    sequence_start: Instant, // When the first byte of the sequence came in
+    taps: Vec<(Key, Instant)>, // Keys tapped, waiting to see if the terminal repeats them
+    held: Vec<(Key, Instant)>, // Keys held down with the last time the terminal sent them
+}
+
+impl Keyboard {
+    /// Creates the keyboard of a terminal
+    ///
+    /// # Arguments
+    ///
+    /// * `kitty` - Whether the terminal reports key events with the kitty keyboard protocol
+    pub fn new(kitty: bool) -> Keyboard {
+        Keyboard {
+            kitty,
+            sequence: Vec::new(),
+            sequence_start: Instant::now(),
+            taps: Vec::new(),
+            held: Vec::new(),
+        }
+    }
+
+    /// Reads a byte that came in from the terminal
+    ///
+    /// # Returns
+    ///
+    /// The key events completed by the byte
+    pub fn feed(&mut self, byte: u8, now: Instant) -> Vec<KeyEvent> {
+        match self.decode(byte, now) {
+            Some(event) => self.key_events(event, now),
+            None => Vec::new(),
+        }
+    }
+
+    /// Finishes the escape sequences nothing followed and releases the held keys the terminal
+    /// stopped repeating
+    ///
+    /// Terminals that report releases never need the keys released
+    pub fn expire(&mut self, now: Instant) -> Vec<KeyEvent> {
+        let mut events = Vec::new();
+
+        if !self.sequence.is_empty() && now - self.sequence_start > ESCAPE_TIMEOUT {
+            let sequence = std::mem::take(&mut self.sequence);
+
+            // An escape on its own is the escape key, and an escape before a character that
+            // usually starts a sequence is that character typed with Alt
+            let key = match sequence.as_slice() {
+                [0x1b] => Some(Key::new(KeyCode::Escape)),
+                [0x1b, byte] => plain_key(*byte).map(|mut key| {
+                    key.modifiers.alt = true;
+                    key
+                }),
+                _ => None,
+            };
+
+            if let Some(key) = key {
+                events.extend(self.key_events(KeyEvent::new(key, KeyState::Press), now));
+            }
+        }
+
+        self.taps.retain(|&(_, time)| now - time <= REPEAT_INTERVAL);
+
+        self.held.retain(|&(key, time)| {
+            let held = now - time <= REPEAT_INTERVAL;
+
+            if !held {
+                events.push(KeyEvent::new(key, KeyState::Release));
+            }
+
+            held
+        });
+
+        events
+    }
+
+    /// Turns a decoded key into the events it gives, timing the repeats of the terminal when it
+    /// does not report releases
+    fn key_events(&mut self, event: KeyEvent, now: Instant) -> Vec<KeyEvent> {
+        if self.kitty {
+            return vec![event];
+        }
+
+        let key = event.key;
+
+        if let Some(held) = self.held.iter_mut().find(|(held, _)| *held == key) {
+            held.1 = now;
+            return vec![KeyEvent::new(key, KeyState::Repeat)];
+        }
+
+        if let Some(index) = self
+            .taps
+            .iter()
+            .position(|&(tap, time)| tap == key && now - time <= REPEAT_INTERVAL)
+        {
+            // The key came again at the terminal's repeat rate, so it is being held down
+            self.taps.remove(index);
+            self.held.push((key, now));
+
+            return vec![KeyEvent::new(key, KeyState::Press)];
+        }
+
+        self.taps.retain(|&(tap, _)| tap != key);
+        self.taps.push((key, now));
+
+        vec![
+            KeyEvent::new(key, KeyState::Press),
+            KeyEvent::new(key, KeyState::Release),
+        ]
+    }
+
+    /// Reads a byte of a key, which is either a plain character, a character of several bytes
+    /// or an escape sequence
+    ///
+    /// # Returns
+    ///
+    /// The key and what happened to it, once the byte completes a key
+    fn decode(&mut self, byte: u8, now: Instant) -> Option<KeyEvent> {
+        // An escape always starts a new sequence, dropping the one that was not finished
+        if byte == 0x1b {
+            self.sequence.clear();
+            self.sequence.push(byte);
+            self.sequence_start = now;
+            return None;
+        }
+
+        if self.sequence.is_empty() {
+            if byte.is_ascii() {
+                return plain_key(byte).map(|key| KeyEvent::new(key, KeyState::Press));
+            }
+
+            self.sequence_start = now;
+        }
+
+        self.sequence.push(byte);
+
+        let event = match self.sequence.as_slice() {
+            [0x1b] => return None,
+            [0x1b, b'[' | b'O'] => return None,
+            // Control sequences are ESC [ followed by parameters and end with a final byte
+            [0x1b, b'[', .., last] if !(0x40..=0x7e).contains(last) => return None,
+            [0x1b, b'[', body @ ..] => control_sequence(body),
+            // Single shift sequences, sent by some terminals for arrows and F1 to F4
+            [0x1b, b'O', last] => ss3_key(*last).map(|key| KeyEvent::new(key, KeyState::Press)),
+            // Alt sends an escape before the key it is held with
+            [0x1b, byte] => plain_key(*byte).map(|mut key| {
+                key.modifiers.alt = true;
+                KeyEvent::new(key, KeyState::Press)
+            }),
+            characters => match std::str::from_utf8(characters) {
+                Ok(text) => text.chars().next().map(|character| {
+                    KeyEvent::new(Key::new(KeyCode::Char(character)), KeyState::Press)
+                }),
+                // A character of several bytes that is not complete yet
+                Err(error) if error.error_len().is_none() => return None,
+                Err(_) => None,
+            },
+        };
+
+        self.sequence.clear();
+
+        event
+    }
+}
+
+/// Returns the key a single byte stands for, control bytes being keys held with Ctrl
+fn plain_key(byte: u8) -> Option<Key> {
+    let code = match byte {
+        b'\r' | b'\n' => KeyCode::Enter,
+        b'\t' => KeyCode::Tab,
+        0x7f => KeyCode::Backspace,
+        0x1b => KeyCode::Escape,
+        0x00 => {
+            return Some(Key::with_modifiers(
+                KeyCode::Char(' '),
+                Modifiers {
+                    ctrl: true,
+                    ..Modifiers::default()
+                },
+            ))
+        }
+        // Ctrl clears the upper bits of the key it is held with, so Ctrl+A sends 0x01
+        0x01..=0x1f => {
+            return Some(Key::with_modifiers(
+                KeyCode::Char((byte | 0x40).to_ascii_lowercase() as char),
+                Modifiers {
+                    ctrl: true,
+                    ..Modifiers::default()
+                },
+            ))
+        }
+        0x20..=0x7e => KeyCode::Char(byte as char),
+        _ => return None,
+    };
+
+    Some(Key::new(code))
+}
+
+/// Returns the key at the end of a single shift sequence, ESC O followed by a letter
+fn ss3_key(last: u8) -> Option<Key> {
+    let code = match last {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        _ => return None,
+    };
+
+    Some(Key::new(code))
+}
+
+/// Reads the key of a control sequence, given what follows ESC [
+///
+/// Sequences are written as ESC [ number ; modifiers : event, followed by a final byte telling
+/// what the number means. Kitty writes every key this way, ending with 'u' for keys given by
+/// their code point, while other terminals only write special keys this way
+fn control_sequence(body: &[u8]) -> Option<KeyEvent> {
+    let (&last, parameters) = body.split_last()?;
+    let parameters = std::str::from_utf8(parameters).ok()?;
+
+    let mut fields = parameters.split(';');
+    let number = fields.next().unwrap_or("");
+    let mut modifier_fields = fields.next().unwrap_or("").split(':');
+    let modifiers = Modifiers::from_parameter(modifier_fields.next().unwrap_or(""));
+
+    let state = match modifier_fields.next() {
+        Some("2") => KeyState::Repeat,
+        Some("3") => KeyState::Release,
+        _ => KeyState::Press,
+    };
+
+    let code = match last {
+        b'u' => {
+            // The code point of the key, followed by the one it types with Shift held
+            let mut codes = number.split(':');
+            let base = match codes.next()?.parse().ok()? {
+                13 => KeyCode::Enter,
+                9 => KeyCode::Tab,
+                127 => KeyCode::Backspace,
+                27 => KeyCode::Escape,
+                code if KITTY_PRIVATE_KEYS.contains(&code) => {
+                    let number = code.checked_sub(KITTY_F13)? + 13;
+
+                    if number > MAX_FUNCTION_KEY as u32 {
+                        return None;
+                    }
+
+                    KeyCode::F(number as u8)
+                }
+                code => KeyCode::Char(char::from_u32(code)?),
+            };
+
+            let code = match codes.next().and_then(|code| code.parse().ok()) {
+                Some(shifted) if modifiers.shift => KeyCode::Char(char::from_u32(shifted)?),
+                _ => base,
+            };
+
+            return Some(KeyEvent {
+                key: Key::with_modifiers(code, modifiers),
+                state,
+                base,
+            });
+        }
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        b'Z' => {
+            // Shift+Tab has a sequence of its own
+            return Some(KeyEvent::new(
+                Key::with_modifiers(
+                    KeyCode::Tab,
+                    Modifiers {
+                        shift: true,
+                        ..modifiers
+                    },
+                ),
+                state,
+            ));
+        }
+        b'~' => match number.parse::<u8>().ok()? {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            number @ 11..=15 => KeyCode::F(number - 10),
+            number @ 17..=21 => KeyCode::F(number - 11),
+            number @ 23..=26 => KeyCode::F(number - 12),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(KeyEvent::new(Key::with_modifiers(code, modifiers), state))
+}
+
+/// Asks the terminal if it supports the kitty keyboard protocol, and turns it on if it does
+///
+/// # Returns
+///
+/// A boolean indicating if the protocol was turned on
+pub fn enable_kitty<W: Write, R: Read>(stdout: &mut W, stdin: &mut Bytes<R>) -> bool {
+    write!(stdout, "{}", KITTY_QUERY).unwrap();
+    stdout.flush().unwrap();
+
+    let start = Instant::now();
+    let mut response = Vec::new();
+
+    // Terminals answer the device attributes query last, ending with 'c'
+    while start.elapsed() < QUERY_TIMEOUT && !response.ends_with(b"c") {
+        match stdin.next() {
+            Some(Ok(byte)) => response.push(byte),
+            _ => thread::sleep(Duration::from_millis(5)),
+        }
+    }
+
+    // Terminals with the protocol answer the flags query with ESC [ ? flags u
+    let supported = response.split(|&byte| byte == 0x1b).any(|answer| {
+        answer
+            .strip_prefix(b"[?")
+            .and_then(|answer| answer.strip_suffix(b"u"))
+            .is_some_and(|flags| flags.iter().all(u8::is_ascii_digit))
+    });
+
+    if supported {
+        write!(stdout, "{}", KITTY_ENABLE).unwrap();
+        stdout.flush().unwrap();
+    }
+
+    supported
+}
+
+/// Turns the kitty keyboard protocol back off before leaving the game
+pub fn disable_kitty<W: Write>(stdout: &mut W) {
+    write!(stdout, "{}", KITTY_DISABLE).unwrap();
+    stdout.flush().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds the bytes of a sequence to a keyboard, returning the events they completed
+    fn feed(keyboard: &mut Keyboard, bytes: &[u8], now: Instant) -> Vec<KeyEvent> {
+        bytes
+            .iter()
+            .flat_map(|&byte| keyboard.feed(byte, now))
+            .collect()
+    }
+
+    /// Returns the states of the events, to compare them without the keys
+    fn states(events: &[KeyEvent]) -> Vec<KeyState> {
+        events.iter().map(|event| event.state).collect()
+    }
+
+    #[test]
+    fn key_tapped_twice_is_never_held() {
+        let mut keyboard = Keyboard::new(false);
+        let start = Instant::now();
+        let tap = [KeyState::Press, KeyState::Release];
+
+        for time in [0, 150] {
+            let now = start + Duration::from_millis(time);
+
+            assert_eq!(states(&keyboard.feed(b'd', now)), tap);
+            assert!(keyboard.expire(now).is_empty());
+        }
+
+        assert!(keyboard
+            .expire(start + Duration::from_millis(400))
+            .is_empty());
+    }
+
+    #[test]
+    fn key_repeated_by_the_terminal_is_held_until_the_repeats_stop() {
+        let mut keyboard = Keyboard::new(false);
+        let start = Instant::now();
+        let at = |time| start + Duration::from_millis(time);
+
+        // The terminal repeats the key after its repeat delay, then at its repeat rate
+        assert_eq!(
+            states(&keyboard.feed(b'd', at(0))),
+            [KeyState::Press, KeyState::Release]
+        );
+        assert_eq!(
+            states(&keyboard.feed(b'd', at(500))),
+            [KeyState::Press, KeyState::Release]
+        );
+        assert_eq!(states(&keyboard.feed(b'd', at(530))), [KeyState::Press]);
+        assert_eq!(states(&keyboard.feed(b'd', at(560))), [KeyState::Repeat]);
+        assert!(keyboard.expire(at(600)).is_empty());
+
+        assert_eq!(states(&keyboard.expire(at(700))), [KeyState::Release]);
+    }
+
+    /// Decodes a sequence on a keyboard with the kitty protocol, which gives every event as is
+    fn decode(sequence: &str) -> Vec<(Key, KeyState)> {
+        feed(
+            &mut Keyboard::new(true),
+            sequence.as_bytes(),
+            Instant::now(),
+        )
+        .into_iter()
+        .map(|event| (event.key, event.state))
+        .collect()
+    }
+
+    fn key(name: &str) -> Key {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn decodes_arrows_of_control_and_single_shift_sequences() {
+        assert_eq!(decode("\x1b[A"), [(key("up"), KeyState::Press)]);
+        assert_eq!(decode("\x1bOD"), [(key("left"), KeyState::Press)]);
+        assert_eq!(decode("\x1b[1;5D"), [(key("ctrl+left"), KeyState::Press)]);
+    }
+
+    #[test]
+    fn decodes_function_keys() {
+        assert_eq!(decode("\x1bOP"), [(key("f1"), KeyState::Press)]);
+        assert_eq!(decode("\x1b[15~"), [(key("f5"), KeyState::Press)]);
+        assert_eq!(decode("\x1b[57376u"), [(key("f13"), KeyState::Press)]);
+    }
+
+    #[test]
+    fn decodes_kitty_releases_and_shifted_keys() {
+        assert_eq!(decode("\x1b[97;1:3u"), [(key("a"), KeyState::Release)]);
+        assert_eq!(decode("\x1b[97:65;2u"), [(key("A"), KeyState::Press)]);
+        assert_eq!(decode("\x1b[9;2u"), [(key("shift+tab"), KeyState::Press)]);
+        assert_eq!(decode("\x1b[27u"), [(key("escape"), KeyState::Press)]);
+    }
+
+    #[test]
+    fn decodes_characters_of_several_bytes() {
+        let mut keyboard = Keyboard::new(true);
+        let now = Instant::now();
+
+        assert!(keyboard.feed(0xc3, now).is_empty());
+        assert_eq!(
+            keyboard.feed(0xa9, now),
+            [KeyEvent::new(key("é"), KeyState::Press)]
+        );
+    }
+
+    #[test]
+    fn lone_escape_is_the_escape_key_once_nothing_follows() {
+        let mut keyboard = Keyboard::new(true);
+        let start = Instant::now();
+
+        assert!(keyboard.feed(0x1b, start).is_empty());
+        assert!(keyboard
+            .expire(start + Duration::from_millis(10))
+            .is_empty());
+        assert_eq!(
+            keyboard.expire(start + ESCAPE_TIMEOUT * 2),
+            [KeyEvent::new(key("escape"), KeyState::Press)]
+        );
+
+        // An escape followed by a key in time is that key held with Alt
+        assert!(keyboard.feed(0x1b, start).is_empty());
+        assert_eq!(
+            keyboard.feed(b'x', start),
+            [KeyEvent::new(key("alt+x"), KeyState::Press)]
+        );
+    }
+
+    #[test]
+    fn release_keeps_the_key_it_was_pressed_as() {
+        let mut keyboard = Keyboard::new(true);
+        let now = Instant::now();
+
+        // 'a' released while Ctrl is held, then while Shift is held
+        for sequence in ["\x1b[97;5:3u", "\x1b[97:65;2:3u"] {
+            let events = feed(&mut keyboard, sequence.as_bytes(), now);
+
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].state, KeyState::Release);
+            assert_eq!(events[0].base, KeyCode::Char('a'));
+        }
+    }
+}
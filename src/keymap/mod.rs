@@ -0,0 +1,338 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::engine::Input;
+use crate::input::{Key, KeyCode};
+
+const KEYMAP_FILE: &str = "rust-tetris/keys.toml"; // Inside the user's config directory
+
+/// Represents something the player can do with a key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateCounterClockwise,
+    Rotate180,
+    Hold,
+    ToggleGhost,
+    Pause,
+    Restart,
+    Quit,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    MenuSelect,
+    MenuBack,
+}
+
+impl Action {
+    /// Every action, in the order they are listed to the player
+    pub const ALL: [Action; 18] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::SoftDrop,
+        Action::HardDrop,
+        Action::RotateClockwise,
+        Action::RotateCounterClockwise,
+        Action::Rotate180,
+        Action::Hold,
+        Action::ToggleGhost,
+        Action::Pause,
+        Action::Restart,
+        Action::Quit,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuLeft,
+        Action::MenuRight,
+        Action::MenuSelect,
+        Action::MenuBack,
+    ];
+
+    /// Returns the name the action is bound with in the keymap file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::SoftDrop => "soft_drop",
+            Action::HardDrop => "hard_drop",
+            Action::RotateClockwise => "rotate_clockwise",
+            Action::RotateCounterClockwise => "rotate_counter_clockwise",
+            Action::Rotate180 => "rotate_180",
+            Action::Hold => "hold",
+            Action::ToggleGhost => "toggle_ghost",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Quit => "quit",
+            Action::MenuUp => "menu_up",
+            Action::MenuDown => "menu_down",
+            Action::MenuLeft => "menu_left",
+            Action::MenuRight => "menu_right",
+            Action::MenuSelect => "menu_select",
+            Action::MenuBack => "menu_back",
+        }
+    }
+
+    /// Checks if the action is used in the menus rather than during a game
+    ///
+    /// Menu actions and game actions are never looked up together, so they can share keys
+    pub fn is_menu(self) -> bool {
+        matches!(
+            self,
+            Action::MenuUp
+                | Action::MenuDown
+                | Action::MenuLeft
+                | Action::MenuRight
+                | Action::MenuSelect
+                | Action::MenuBack
+        )
+    }
+
+    /// Returns the input the action gives the engine, if it acts on the current tetromino
+    pub fn input(self) -> Option<Input> {
+        match self {
+            Action::MoveLeft => Some(Input::MoveLeft),
+            Action::MoveRight => Some(Input::MoveRight),
+            Action::SoftDrop => Some(Input::SoftDrop),
+            Action::HardDrop => Some(Input::HardDrop),
+            Action::RotateClockwise => Some(Input::RotateClockwise),
+            Action::RotateCounterClockwise => Some(Input::RotateCounterClockwise),
+            Action::Rotate180 => Some(Input::Rotate180),
+            Action::Hold => Some(Input::Hold),
+            _ => None,
+        }
+    }
+
+    /// Returns the keys the action is bound to when the keymap file does not change them
+    fn default_keys(self) -> Vec<Key> {
+        let codes = match self {
+            Action::MoveLeft => vec![KeyCode::Char('a'), KeyCode::Left],
+            Action::MoveRight => vec![KeyCode::Char('d'), KeyCode::Right],
+            Action::SoftDrop => vec![KeyCode::Char('s'), KeyCode::Down],
+            Action::HardDrop => vec![KeyCode::Char(' ')],
+            Action::RotateClockwise => vec![KeyCode::Char('r'), KeyCode::Up],
+            Action::RotateCounterClockwise => vec![KeyCode::Char('e')],
+            Action::Rotate180 => vec![KeyCode::Char('w')],
+            Action::Hold => vec![KeyCode::Char('c')],
+            Action::ToggleGhost => vec![KeyCode::Char('g')],
+            Action::Pause => vec![KeyCode::Char('p')],
+            Action::Restart => vec![KeyCode::Char('n')],
+            Action::Quit => vec![KeyCode::Char('q')],
+            Action::MenuUp => vec![KeyCode::Up, KeyCode::Char('w')],
+            Action::MenuDown => vec![KeyCode::Down, KeyCode::Char('s')],
+            Action::MenuLeft => vec![KeyCode::Left, KeyCode::Char('a')],
+            Action::MenuRight => vec![KeyCode::Right, KeyCode::Char('d')],
+            Action::MenuSelect => vec![KeyCode::Enter, KeyCode::Char(' ')],
+            Action::MenuBack => vec![KeyCode::Escape, KeyCode::Char('q')],
+        };
+
+        codes.into_iter().map(Key::new).collect()
+    }
+}
+
+/// Represents the keys every action is bound to
+///
+/// An action can be bound to several keys, but a key only ever triggers one action during a
+/// game and one action in the menus
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_keys()))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Returns where the keymap file is looked for, inside $XDG_CONFIG_HOME or ~/.config
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+        Some(config_dir.join(KEYMAP_FILE))
+    }
+
+    /// Loads the keymap file from the user's config directory, falling back on the default keys
+    /// when there is no file
+    pub fn load() -> Result<Keymap, String> {
+        let Some(path) = Keymap::path() else {
+            return Ok(Keymap::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                Keymap::from_toml(&text).map_err(|error| format!("{}: {}", path.display(), error))
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Keymap::default()),
+            Err(error) => Err(format!("{}: {}", path.display(), error)),
+        }
+    }
+
+    /// Reads a keymap written as a TOML table of action names to lists of key names
+    ///
+    /// ```toml
+    /// move_left = ["a", "left"]
+    /// hard_drop = ["space"]
+    /// ```
+    ///
+    /// Actions missing from the table keep their default keys
+    pub fn from_toml(text: &str) -> Result<Keymap, String> {
+        let table: toml::Table = text.parse().map_err(|error| format!("{}", error))?;
+        let mut keymap = Keymap::default();
+
+        for (name, value) in table {
+            let action = Action::ALL
+                .into_iter()
+                .find(|action| action.name() == name)
+                .ok_or_else(|| format!("Unknown action '{}'", name))?;
+
+            // A single key can be written without the list around it
+            let values = match value {
+                toml::Value::Array(values) => values,
+                value => vec![value],
+            };
+
+            let keys = values
+                .iter()
+                .map(|value| match value.as_str() {
+                    Some(key) => key.parse(),
+                    None => Err(format!("The keys of '{}' must be strings", name)),
+                })
+                .collect::<Result<Vec<Key>, String>>()?;
+
+            keymap.bind(action, keys);
+        }
+
+        keymap.check()?;
+
+        Ok(keymap)
+    }
+
+    /// Binds an action to the given keys, replacing the keys it was bound to
+    pub fn bind(&mut self, action: Action, keys: Vec<Key>) {
+        if let Some(binding) = self.bindings.iter_mut().find(|(bound, _)| *bound == action) {
+            binding.1 = keys;
+        }
+    }
+
+    /// Returns the keys an action is bound to
+    pub fn keys_for(&self, action: Action) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys)
+    }
+
+    /// Returns the action a key triggers during a game, if it is bound to one
+    pub fn action_for(&self, key: Key) -> Option<Action> {
+        self.find_action(key, false)
+    }
+
+    /// Returns the action a key triggers in the menus, if it is bound to one
+    pub fn menu_action_for(&self, key: Key) -> Option<Action> {
+        self.find_action(key, true)
+    }
+
+    /// Returns the names of the keys an action is bound to, for showing them to the player
+    pub fn describe(&self, action: Action) -> String {
+        let names: Vec<String> = self.keys_for(action).iter().map(Key::to_string).collect();
+
+        names.join("/")
+    }
+
+    /// Checks that no key is bound to two actions used at the same time, and that the game and
+    /// the menus can always be left
+    pub fn check(&self) -> Result<(), String> {
+        for (index, (action, keys)) in self.bindings.iter().enumerate() {
+            for key in keys {
+                if let Some((other, _)) =
+                    self.bindings[index + 1..]
+                        .iter()
+                        .find(|(other, other_keys)| {
+                            other.is_menu() == action.is_menu() && other_keys.contains(key)
+                        })
+                {
+                    return Err(format!(
+                        "The key '{}' is bound to both {} and {}",
+                        key,
+                        action.name(),
+                        other.name()
+                    ));
+                }
+            }
+        }
+
+        for action in [Action::Quit, Action::MenuBack] {
+            if self.keys_for(action).is_empty() {
+                return Err(format!(
+                    "At least one key must be bound to {}",
+                    action.name()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the first action of the given kind bound to a key
+    fn find_action(&self, key: Key, menu: bool) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(action, keys)| action.is_menu() == menu && keys.contains(&key))
+            .map(|&(action, _)| action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> Key {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn key_bound_to_two_game_actions_is_refused() {
+        let error = Keymap::from_toml("hard_drop = \"a\"").unwrap_err();
+
+        assert!(error.contains("'a'"), "{}", error);
+    }
+
+    #[test]
+    fn game_and_menu_actions_can_share_a_key() {
+        let keymap = Keymap::from_toml("hard_drop = \"enter\"\nmenu_select = \"enter\"").unwrap();
+
+        assert_eq!(keymap.action_for(key("enter")), Some(Action::HardDrop));
+        assert_eq!(
+            keymap.menu_action_for(key("enter")),
+            Some(Action::MenuSelect)
+        );
+    }
+
+    #[test]
+    fn keymap_without_a_quit_key_is_refused() {
+        assert!(Keymap::from_toml("quit = []").is_err());
+        assert!(Keymap::from_toml("menu_back = []").is_err());
+    }
+
+    #[test]
+    fn shift_is_written_as_the_character_it_types() {
+        assert_eq!(key("shift+a"), key("A"));
+
+        let keymap = Keymap::from_toml("hold = \"shift+c\"").unwrap();
+        assert_eq!(keymap.action_for(key("C")), Some(Action::Hold));
+    }
+}
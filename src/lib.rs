@@ -1,244 +1,125 @@
-const WIDTH: usize = 12; // 2 more to account for the borders
-const HEIGHT: usize = 40;
-
-use std::io::Bytes;
-
-extern crate termion;
-use std::io::Write;
-use termion::AsyncReader;
-
-use std::thread;
+use std::str::FromStr;
 use std::time::Duration;
 
-use std::sync::mpsc;
-
 pub mod tetromino;
 
-use crate::tetromino::characters::TetrominoCharacter;
-use crate::tetromino::tetromino::Tetromino;
-
+pub mod board;
 pub mod display;
-
-pub struct GameConfig<'a> {
-    pub screen: [[&'a str; WIDTH]; HEIGHT],
-    pub current_tetromino: Tetromino,
-    pub game_borders: [[bool; WIDTH]; HEIGHT + 1],
-    pub built_tetrominoes: [[TetrominoCharacter; WIDTH]; HEIGHT],
-    pub stdout: termion::raw::RawTerminal<std::io::Stdout>,
-    pub stdin: Bytes<AsyncReader>,
+pub mod engine;
+pub mod gravity;
+pub mod handling;
+pub mod input;
+pub mod keymap;
+pub mod menu;
+pub mod randomizer;
+pub mod records;
+pub mod ruleset;
+pub mod scoring;
+pub mod timing;
+
+use crate::board::BoardSize;
+use crate::handling::Handling;
+use crate::ruleset::Ruleset;
+
+/// Represents the kinds of game the player can choose from the menu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    /// An endless game, played until the stack tops out
+    Marathon,
+    /// A race to clear the given number of lines
+    Sprint(u32),
 }
 
-pub fn run(mut game_config: GameConfig) {
-    let mut score: u32 = 0;
-
-    display::display_screen(
-        &game_config.screen,
-        &mut game_config.current_tetromino,
-        &mut game_config.stdout,
-        &mut game_config.built_tetrominoes,
-        score,
-    );
-
-    let (tx, rx) = mpsc::channel();
-    let mut movement_counter: i32 = 1;
-
-    // Spanws a thread that handles updating movement_counters and sends
-    // true when the tetromino's y position should go down by one following the game logic
-    thread::spawn(move || loop {
-        thread::sleep(Duration::from_millis(100));
-
-        if movement_counter % 2 == 0 {
-            tx.send(true).unwrap();
-        } else {
-            tx.send(false).unwrap();
-        }
-
-        movement_counter += 1;
-    });
-
-    loop {
-        write!(game_config.stdout, "{}", termion::clear::CurrentLine).unwrap();
-
-        let b = game_config.stdin.next();
-
-        if let Some(Ok(b'q')) = b {
-            break;
-        }
-        if let Some(Ok(b'a')) = b {
-            game_config
-                .current_tetromino
-                .move_tetromino(-1, 0, &mut game_config.game_borders);
-
-            display::display_screen(
-                &game_config.screen,
-                &mut game_config.current_tetromino,
-                &mut game_config.stdout,
-                &mut game_config.built_tetrominoes,
-                score,
-            );
-        }
-        if let Some(Ok(b'd')) = b {
-            game_config
-                .current_tetromino
-                .move_tetromino(1, 0, &mut game_config.game_borders);
-
-            display::display_screen(
-                &game_config.screen,
-                &mut game_config.current_tetromino,
-                &mut game_config.stdout,
-                &mut game_config.built_tetrominoes,
-                score,
-            );
-        }
-        if let Some(Ok(b'r')) = b {
-            game_config
-                .current_tetromino
-                .rotate(90, &mut game_config.game_borders);
-
-            display::display_screen(
-                &game_config.screen,
-                &mut game_config.current_tetromino,
-                &mut game_config.stdout,
-                &mut game_config.built_tetrominoes,
-                score,
-            );
-        }
-        if let Some(Ok(b's')) = b {
-            game_config
-                .current_tetromino
-                .move_tetromino(0, 1, &mut game_config.game_borders);
-
-            display::display_screen(
-                &game_config.screen,
-                &mut game_config.current_tetromino,
-                &mut game_config.stdout,
-                &mut game_config.built_tetrominoes,
-                score,
-            );
-        }
-        thread::sleep(Duration::from_millis(1));
-
-        if game_config.current_tetromino.stationary {
-            tetromino::create_tetronimo(&mut game_config.current_tetromino);
+impl GameMode {
+    /// Every game mode, in the order they are listed in the menu
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Marathon,
+        GameMode::Sprint(20),
+        GameMode::Sprint(40),
+        GameMode::Sprint(100),
+    ];
+
+    /// Returns the name the mode is stored with
+    pub fn name(self) -> String {
+        match self {
+            GameMode::Marathon => String::from("marathon"),
+            GameMode::Sprint(goal) => format!("sprint-{}", goal),
         }
+    }
 
-        if rx.recv() == Ok(true) {
-            game_config
-                .current_tetromino
-                .move_tetromino(0, 1, &mut game_config.game_borders);
+    /// Returns the name the mode is shown with
+    pub fn title(self) -> String {
+        match self {
+            GameMode::Marathon => String::from("Marathon"),
+            GameMode::Sprint(goal) => format!("Sprint {}", goal),
         }
-
-        display::display_screen(
-            &game_config.screen,
-            &mut game_config.current_tetromino,
-            &mut game_config.stdout,
-            &mut game_config.built_tetrominoes,
-            score,
-        );
-
-        move_to_built(
-            &mut game_config.current_tetromino,
-            &mut game_config.built_tetrominoes,
-        );
-
-        check_complete_line(&mut game_config.built_tetrominoes, &mut score);
-
-        remake_gameborders(
-            &mut game_config.game_borders,
-            &mut game_config.built_tetrominoes,
-        );
-
-        game_config.stdout.flush().unwrap();
     }
-}
 
-pub fn move_to_built(
-    tetromino: &mut Tetromino,
-    built_tetrominoes: &mut [[TetrominoCharacter; WIDTH]; HEIGHT],
-) {
-    if tetromino.stationary {
-        for character in &tetromino.first_line.characters {
-            let x = character.x;
-            let y = character.y;
-            built_tetrominoes[y as usize][x as usize] = *character;
-        }
-        for character in &tetromino.second_line.characters {
-            let x = character.x;
-            let y = character.y;
-            built_tetrominoes[y as usize][x as usize] = *character;
-        }
-        for character in &tetromino.third_line.characters {
-            let x = character.x;
-            let y = character.y;
-            built_tetrominoes[y as usize][x as usize] = *character;
-        }
-        for character in &tetromino.fourth_line.characters {
-            let x = character.x;
-            let y = character.y;
-            built_tetrominoes[y as usize][x as usize] = *character;
+    /// Returns the number of lines that ends the game, if the mode has one
+    pub fn line_goal(self) -> Option<u32> {
+        match self {
+            GameMode::Marathon => None,
+            GameMode::Sprint(goal) => Some(goal),
         }
     }
 }
 
-fn check_complete_line(
-    built_tetrominoes: &mut [[TetrominoCharacter; WIDTH]; HEIGHT],
-    score: &mut u32,
-) -> bool {
-    let mut complete_line = false;
-    let mut lines_cleared = 0;
+impl FromStr for GameMode {
+    type Err = String;
 
-    for i in 0..HEIGHT {
-        let mut line_is_complete = true;
-
-        for j in 1..WIDTH - 1 {
-            if built_tetrominoes[i][j].value.is_empty() {
-                line_is_complete = false;
-                break;
-            }
-        }
-
-        if line_is_complete {
-            complete_line = true;
-            lines_cleared += 1;
-
-            // Clear the current line
-            for j in 0..WIDTH {
-                built_tetrominoes[i][j] = TetrominoCharacter::default();
-            }
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        GameMode::ALL
+            .into_iter()
+            .find(|mode| mode.name() == name)
+            .ok_or_else(|| format!("Unknown game mode '{}'", name))
+    }
+}
 
-            // Shift all rows above the current line down by one row
-            for k in (1..=i).rev() {
-                for j in 0..WIDTH {
-                    built_tetrominoes[k][j] = built_tetrominoes[k - 1][j];
-                    // Move character down by one row
-                    built_tetrominoes[k][j].move_character(0, 1);
-                }
-            }
+/// Represents the settings a game is created with
+#[derive(Clone, Copy, Debug)]
+pub struct GameConfig {
+    pub mode: GameMode,
+    pub ruleset: Ruleset,
+    pub board: BoardSize,
+    pub handling: Handling,
+    pub seed: Option<u64>, // Plays the same piece sequence on every game when set
+    pub ghost: bool,       // Shows where the current tetromino would land
+    pub stats: bool,       // Shows frame timing statistics
+}
 
-            // Clear the top row since it has been shifted down
-            for j in 0..WIDTH {
-                built_tetrominoes[0][j] = TetrominoCharacter::default();
-            }
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            mode: GameMode::Marathon,
+            ruleset: Ruleset::default(),
+            board: BoardSize::default(),
+            handling: Handling::default(),
+            seed: None,
+            ghost: true,
+            stats: false,
         }
     }
+}
 
-    // Update score based on the number of lines cleared
-    *score += lines_cleared * 100;
-
-    complete_line
+/// Represents the reason why a game came to an end
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EndReason {
+    /// The player quit the game
+    Quit,
+    /// A new tetromino spawned overlapping the built tetrominoes
+    BlockOut,
+    /// A tetromino locked completely above the visible field
+    LockOut,
+    /// The player cleared the lines the mode asked for
+    Finished,
 }
 
-fn remake_gameborders(
-    game_borders: &mut [[bool; WIDTH]; HEIGHT + 1],
-    built_tetrominoes: &mut [[TetrominoCharacter; WIDTH]; HEIGHT],
-) {
-    for i in 0..HEIGHT {
-        for j in 0..WIDTH {
-            if built_tetrominoes[i][j].value.is_empty() {
-                game_borders[i][j] = false;
-            } else {
-                game_borders[i][j] = true;
-            }
-        }
-    }
+/// Represents the final results of a game
+#[derive(Clone, Copy, Debug)]
+pub struct GameOutcome {
+    pub reason: EndReason,
+    pub score: u32,
+    pub lines: u32,
+    pub duration: Duration,
+    pub seed: u64, // Replays the same piece sequence when passed back through the config
 }
@@ -10,60 +10,783 @@ __          __   _ _   _               _                  _
                                              |___/
  */
 
-use std::io::Read;
+use std::collections::VecDeque;
+use std::io::{Bytes, Read};
+use std::str::FromStr;
 
 extern crate termion;
-use std::io::{stdout, Write};
-use termion::raw::IntoRawMode;
-use termion::{async_stdin, clear};
+use std::io::{stdout, Stdout, Write};
+use termion::raw::{IntoRawMode, RawTerminal};
+use termion::{async_stdin, clear, AsyncReader};
 
-use rust_tetris::tetromino;
-use rust_tetris::tetromino::characters::TetrominoCharacter;
-use rust_tetris::tetromino::tetromino::Tetromino;
-use rust_tetris::{display, run, GameConfig};
+use std::thread;
+use std::time::{Duration, Instant};
 
-const WIDTH: usize = 12; // 2 more to account for the borders
-const HEIGHT: usize = 40;
+use rust_tetris::board::BoardSize;
+use rust_tetris::engine::{Engine, Event, Input, FRAME_RATE, MAX_PREVIEW};
+use rust_tetris::input::{self, KeyCode, KeyEvent, KeyState, Keyboard};
+use rust_tetris::keymap::{Action, Keymap};
+use rust_tetris::menu::{MenuEntry, Selection, Setting};
+use rust_tetris::records::{HighScore, HighScores, Playback, Replay, Replays};
+use rust_tetris::tetromino::kind::PieceKind;
+use rust_tetris::timing::FixedTimestep;
+use rust_tetris::{display, EndReason, GameConfig, GameMode, GameOutcome};
 
-/* Game loop */
+const STATS_REFRESH_FRAMES: u64 = 30; // Frames between redraws of the frame statistics
 
-fn main() {
-    let mut screen: [[&str; WIDTH]; HEIGHT] = [[""; WIDTH]; HEIGHT];
-    let mut unrendered_tetrominoes_list: Tetromino = Tetromino::blank_tetromino(0);
+const USAGE: &str = "Usage: rust-tetris [options]
 
-    let mut game_borders: [[bool; WIDTH]; HEIGHT + 1] = [[false; WIDTH]; HEIGHT + 1];
-    for x in 0..WIDTH {
-        game_borders[HEIGHT][x] = true;
-    }
+Options:
+    --seed <number>          Deals the same sequence of pieces on every game
+    --randomizer <name>      random, 7-bag, 14-bag, classic or tgm
+    --spawn <rule>           guideline or classic
+    --infinite-hold          Allows holding more than once per piece
+    --kicks-180 <table>      Wall kicks of 180 rotations: none, srs+ or tetrio
+    --preview <0-6>          Number of upcoming pieces shown
+    --board <WxH|big>        Board size, 10x38 by default, big draws a 5x19 board twice as large
+    --hidden <rows>          Rows above the visible field where pieces spawn, at least 2
+    --no-ghost               Hides the ghost piece (toggle in game with g)
+    --lock-delay <ms>        Time a landed piece waits before locking
+    --lock-reset <rule>      infinite, move or step
+    --gravity <curve>        guideline or nes
+    --level <number>         Level the game starts on
+    --lines-per-level <n>    Lines to clear to go up one level
+    --scoring <table>        guideline, nes or tgm
+    --das <ms>               Time a side key is held before it starts repeating
+    --arr <ms>               Time between repeated moves, 0 moves straight to the wall
+    --dcd <ms>               Time repeating waits after a rotation or a new piece
+    --sdf <factor>           How many times faster than gravity soft drop falls
+    --stats                  Shows frame timing statistics";
 
-    // When the tetrominoes' stationary state is reached, they are added to the built_tetrominoes array
-    // and removed from the unredered_tetrominoes_list
-    //
-    // The built_tetrominoes array is used to check for collisions with the tetrominoes that have already
-    // been built. Also, the built_tetrominoes array is used to render the tetrominoes that have already
-    // been built and to handle the scoring system.
-    //
-    // Also helps in reducing the number of computations per second needed to render moving tetrominoes as the unredered_tetrominoes_list reduces in size
+/* Game loop */
 
-    let built_tetrominoes: [[TetrominoCharacter; WIDTH]; HEIGHT] =
-        [[TetrominoCharacter::default(); WIDTH]; HEIGHT];
+fn main() {
+    let config = match parse_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let keymap = match Keymap::load() {
+        Ok(keymap) => keymap,
+        Err(message) => {
+            eprintln!("Invalid key bindings: {}", message);
+            std::process::exit(2);
+        }
+    };
 
-    display::create_screen(&mut screen);
-    tetromino::create_tetronimo(&mut unrendered_tetrominoes_list);
+    // A broken high score file does not keep the game from being played. It is left as it is
+    // until a new high score replaces it, and reported once the screen is given back
+    let (high_scores, high_scores_error) = match HighScores::load() {
+        Ok(high_scores) => (high_scores, None),
+        Err(message) => (HighScores::default(), Some(message)),
+    };
 
     let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = async_stdin().bytes();
+
+    // The async reader is fed from an in-memory channel, so reading it byte by byte is cheap
+    #[allow(clippy::unbuffered_bytes)]
+    let mut stdin = async_stdin().bytes();
 
     writeln!(stdout, "{}{}", clear::All, termion::cursor::Hide).unwrap();
 
-    let game_config = GameConfig {
-        screen,
-        current_tetromino: unrendered_tetrominoes_list,
-        game_borders,
-        built_tetrominoes,
+    // Terminals with the kitty keyboard protocol report key releases, the others are timed
+    let kitty = input::enable_kitty(&mut stdout, &mut stdin);
+
+    let mut session = Session {
+        config,
+        keymap,
         stdout,
         stdin,
+        keyboard: Keyboard::new(kitty),
+        timestep: FixedTimestep::new(FRAME_RATE),
+        high_scores,
+        replays: Replays::default(),
+        menu_actions: VecDeque::new(),
+        last_outcome: None,
     };
 
-    run(game_config);
+    session.main_menu();
+
+    if kitty {
+        input::disable_kitty(&mut session.stdout);
+    }
+
+    write!(
+        session.stdout,
+        "{}{}{}",
+        clear::All,
+        termion::cursor::Goto(1, 1),
+        termion::cursor::Show
+    )
+    .unwrap();
+    session.stdout.flush().unwrap();
+
+    // Leaves raw mode before printing the results
+    let Session {
+        stdout,
+        timestep,
+        last_outcome,
+        ..
+    } = session;
+    drop(stdout);
+
+    if let Some(message) = high_scores_error {
+        eprintln!(
+            "Invalid high scores, the game started without them: {}",
+            message
+        );
+    }
+
+    if let Some(outcome) = last_outcome {
+        println!(
+            "Final score: {} | Lines: {} | Time: {} | Seed: {}",
+            outcome.score,
+            outcome.lines,
+            display::clock(outcome.duration),
+            outcome.seed
+        );
+    }
+
+    if config.stats {
+        let stats = timestep.stats;
+
+        println!(
+            "Frames: {} | Late: {} | Dropped: {} | Rendered: {} | Render time: {:.2} ms average, {:.2} ms worst",
+            stats.frames,
+            stats.late_frames,
+            stats.dropped_frames,
+            stats.renders,
+            stats.average_render().as_secs_f64() * 1000.0,
+            stats.worst_render.as_secs_f64() * 1000.0
+        );
+    }
+}
+
+/// Represents the terminal the game runs in, and what is kept from one game to the next
+struct Session {
+    config: GameConfig, // The settings of the next game, changed from the settings screen
+    keymap: Keymap,
+    stdout: RawTerminal<Stdout>,
+    stdin: Bytes<AsyncReader>,
+    keyboard: Keyboard,
+    timestep: FixedTimestep,
+    high_scores: HighScores,
+    replays: Replays,
+    menu_actions: VecDeque<Action>, // Menu keys pressed ahead of the screen that handles them
+    last_outcome: Option<GameOutcome>, // The results of the last game played, printed on exit
+}
+
+impl Session {
+    /// Shows the main menu until the player quits
+    fn main_menu(&mut self) {
+        let entries = MenuEntry::all();
+        let titles: Vec<String> = entries.iter().map(|entry| entry.title()).collect();
+        let mut selection = Selection::new(entries.len());
+
+        loop {
+            let hint = format!(
+                "{}: choose   {}: quit",
+                self.keymap.describe(Action::MenuSelect),
+                self.keymap.describe(Action::MenuBack)
+            );
+            display::display_menu(
+                &mut self.stdout,
+                "RUST TETRIS",
+                &titles,
+                Some(selection.index),
+                &hint,
+            );
+
+            match self.menu_action() {
+                Action::MenuUp => selection.up(),
+                Action::MenuDown => selection.down(),
+                Action::MenuSelect => match entries[selection.index] {
+                    MenuEntry::Play(mode) => self.play(mode),
+                    MenuEntry::Settings => self.show_settings(),
+                    MenuEntry::KeyBindings => self.show_key_bindings(),
+                    MenuEntry::HighScores => self.show_high_scores(),
+                    MenuEntry::Replays => self.show_replays(),
+                    MenuEntry::Quit => return,
+                },
+                // Going back from the main menu quits, so it can always be left
+                Action::MenuBack => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// Shows the settings the next games are played with, changed with the side keys
+    fn show_settings(&mut self) {
+        let mut selection = Selection::new(Setting::ALL.len());
+
+        loop {
+            let entries: Vec<String> = Setting::ALL
+                .iter()
+                .map(|setting| format!("{:<18}{}", setting.title(), setting.value(&self.config)))
+                .collect();
+            let hint = format!(
+                "{} {}: change   {}: back",
+                self.keymap.describe(Action::MenuLeft),
+                self.keymap.describe(Action::MenuRight),
+                self.keymap.describe(Action::MenuBack)
+            );
+            display::display_menu(
+                &mut self.stdout,
+                "SETTINGS",
+                &entries,
+                Some(selection.index),
+                &hint,
+            );
+
+            let setting = Setting::ALL[selection.index];
+
+            match self.menu_action() {
+                Action::MenuUp => selection.up(),
+                Action::MenuDown => selection.down(),
+                Action::MenuLeft => setting.change(&mut self.config, false),
+                Action::MenuRight | Action::MenuSelect => setting.change(&mut self.config, true),
+                Action::MenuBack => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// Shows the keys every action is bound to
+    fn show_key_bindings(&mut self) {
+        let entries: Vec<String> = Action::ALL
+            .iter()
+            .map(|&action| format!("{:<26}{}", action.name(), self.keymap.describe(action)))
+            .collect();
+        let path = Keymap::path().map_or_else(String::new, |path| path.display().to_string());
+        let hint = format!(
+            "Bindings are read from {}   {}: back",
+            path,
+            self.keymap.describe(Action::MenuBack)
+        );
+        display::display_menu(&mut self.stdout, "KEY BINDINGS", &entries, None, &hint);
+
+        while self.menu_action() != Action::MenuBack {}
+    }
+
+    /// Shows the best results of every mode, one mode at a time
+    fn show_high_scores(&mut self) {
+        let mut mode = Selection::new(GameMode::ALL.len());
+
+        loop {
+            let shown = GameMode::ALL[mode.index];
+            let mut entries: Vec<String> = self
+                .high_scores
+                .top(shown)
+                .enumerate()
+                .map(|(rank, score)| match shown {
+                    GameMode::Marathon => format!(
+                        "{:>2}. {:>9}   {:>4} lines   {}",
+                        rank + 1,
+                        score.score,
+                        score.lines,
+                        display::clock(score.duration)
+                    ),
+                    GameMode::Sprint(_) => format!(
+                        "{:>2}. {}   {:>9}",
+                        rank + 1,
+                        display::precise_clock(score.duration),
+                        score.score
+                    ),
+                })
+                .collect();
+
+            if entries.is_empty() {
+                entries.push(String::from("No games played yet"));
+            }
+
+            let title = format!("HIGH SCORES - {}", shown.title());
+            let hint = format!(
+                "{} {}: mode   {}: back",
+                self.keymap.describe(Action::MenuLeft),
+                self.keymap.describe(Action::MenuRight),
+                self.keymap.describe(Action::MenuBack)
+            );
+            display::display_menu(&mut self.stdout, &title, &entries, None, &hint);
+
+            match self.menu_action() {
+                Action::MenuLeft => mode.up(),
+                Action::MenuRight => mode.down(),
+                Action::MenuBack => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// Shows the games played during the session, to pick one to watch again
+    fn show_replays(&mut self) {
+        let mut selection = Selection::new(self.replays.len());
+
+        loop {
+            let mut entries: Vec<String> = self
+                .replays
+                .iter()
+                .filter_map(|replay| {
+                    let outcome = replay.outcome?;
+
+                    Some(format!(
+                        "{:<10}{:>9}   {:>4} lines   {}",
+                        replay.config.mode.title(),
+                        outcome.score,
+                        outcome.lines,
+                        display::clock(outcome.duration)
+                    ))
+                })
+                .collect();
+
+            let selected = (!entries.is_empty()).then_some(selection.index);
+
+            if entries.is_empty() {
+                entries.push(String::from("No games played yet"));
+            }
+
+            let hint = format!(
+                "{}: watch   {}: back",
+                self.keymap.describe(Action::MenuSelect),
+                self.keymap.describe(Action::MenuBack)
+            );
+            display::display_menu(&mut self.stdout, "REPLAYS", &entries, selected, &hint);
+
+            match self.menu_action() {
+                Action::MenuUp => selection.up(),
+                Action::MenuDown => selection.down(),
+                Action::MenuSelect if !self.replays.is_empty() => self.watch(selection.index),
+                Action::MenuBack => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// Plays a recorded game back at the speed it was played, until it ends or the player goes
+    /// back to the list
+    fn watch(&mut self, index: usize) {
+        let Some(replay) = self.replays.get(index).cloned() else {
+            return;
+        };
+
+        let mut playback = Playback::new(&replay);
+        let show_ghost = replay.config.ghost;
+
+        self.timestep.reset();
+        self.draw(&playback.engine, show_ghost);
+
+        while !playback.is_finished() {
+            let back = self.read_keys().into_iter().any(|key| {
+                key.state == KeyState::Press
+                    && self.keymap.menu_action_for(key.key) == Some(Action::MenuBack)
+            });
+
+            if back {
+                return;
+            }
+
+            let previous_view = view_of(&playback.engine);
+
+            let frames = self.timestep.advance();
+            let events = playback.advance(frames);
+
+            if !events.is_empty() || view_of(&playback.engine) != previous_view {
+                self.draw(&playback.engine, show_ghost);
+            }
+
+            thread::sleep(self.timestep.until_next_frame());
+        }
+
+        // The end of the game stays on screen until the player goes back
+        write!(
+            self.stdout,
+            "{}{} End of replay   {}: back",
+            termion::cursor::Goto(12, 2),
+            clear::CurrentLine,
+            self.keymap.describe(Action::MenuBack)
+        )
+        .unwrap();
+        self.stdout.flush().unwrap();
+
+        while self.menu_action() != Action::MenuBack {}
+    }
+
+    /// Plays games of the given mode until the player goes back to the menu, starting a new game
+    /// every time the player restarts, either during a game or from the game over screen
+    fn play(&mut self, mode: GameMode) {
+        let config = GameConfig {
+            mode,
+            ..self.config
+        };
+        let mut engine = new_engine(config);
+        let mut replay = Replay::new(config, engine.seed);
+        let mut show_ghost = config.ghost;
+        let mut paused = false;
+        let mut held: Vec<(KeyCode, Input)> = Vec::new(); // The inputs the keys held down pressed
+
+        // The time spent in the menu is not played back, nor are the keys pressed there
+        self.timestep.reset();
+        self.menu_actions.clear();
+        self.draw(&engine, show_ghost);
+
+        loop {
+            let mut events = Vec::new();
+            let mut redraw = false;
+
+            // Every key that came in since the last frame is handled before simulating
+            for key in self.read_keys() {
+                // A key releases the input it pressed, even when the modifiers held with it
+                // changed in between and it no longer stands for the same action
+                if key.state == KeyState::Release {
+                    if let Some(index) = held.iter().position(|&(base, _)| base == key.base) {
+                        let (_, input) = held.remove(index);
+                        replay.record(engine.frames, input, false);
+                        engine.release(input);
+                    }
+
+                    continue;
+                }
+
+                let Some(action) = self.keymap.action_for(key.key) else {
+                    continue;
+                };
+
+                match (action, key.state) {
+                    (Action::Quit, KeyState::Press) => {
+                        self.finish_game(&engine, replay, EndReason::Quit);
+                        return;
+                    }
+                    (Action::Pause, KeyState::Press) => {
+                        paused = !paused;
+                        redraw = true;
+                    }
+                    (Action::Restart, KeyState::Press) => {
+                        self.finish_game(&engine, replay, EndReason::Quit);
+
+                        engine = new_engine(config);
+                        replay = Replay::new(config, engine.seed);
+                        events.clear();
+                        held.clear();
+                        paused = false;
+                        redraw = true;
+
+                        // The time spent on the previous game is not played back
+                        self.timestep.reset();
+                    }
+                    (Action::ToggleGhost, KeyState::Press) => {
+                        show_ghost = !show_ghost;
+                        redraw = true;
+                    }
+                    (action, KeyState::Press) if !paused => {
+                        if let Some(input) = action.input() {
+                            held.retain(|&(base, _)| base != key.base);
+                            held.push((key.base, input));
+
+                            replay.record(engine.frames, input, true);
+                            events.extend(engine.press(input));
+                            redraw = true;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            // Nothing is simulated while paused, and the time spent paused is not played back
+            if paused {
+                self.timestep.reset();
+
+                if redraw {
+                    display::display_pause(&mut self.stdout, &self.keymap);
+                }
+
+                thread::sleep(self.timestep.until_next_frame());
+                continue;
+            }
+
+            let previous_frames = self.timestep.stats.frames;
+            let previous_view = view_of(&engine);
+
+            let frames = self.timestep.advance();
+            events.extend(engine.tick(frames));
+
+            // The screen is only drawn again when something visible changed
+            redraw |= !events.is_empty() || view_of(&engine) != previous_view;
+            redraw |= config.stats
+                && self.timestep.stats.frames / STATS_REFRESH_FRAMES
+                    != previous_frames / STATS_REFRESH_FRAMES;
+
+            if redraw {
+                self.draw(&engine, show_ghost);
+            } else if mode.line_goal().is_some() && frames > 0 {
+                // The timer of a sprint changes on every frame
+                display::display_hud(&engine, &mut self.stdout);
+                self.stdout.flush().unwrap();
+            }
+
+            let Some(reason) = events.iter().find_map(|event| match event {
+                Event::GameOver(reason) => Some(*reason),
+                _ => None,
+            }) else {
+                thread::sleep(self.timestep.until_next_frame());
+                continue;
+            };
+
+            match mode {
+                GameMode::Marathon => display::display_game_over(
+                    &mut self.stdout,
+                    &self.keymap,
+                    engine.score,
+                    engine.lines,
+                    engine.duration(),
+                ),
+                GameMode::Sprint(_) => {
+                    // The personal best is looked up before this game can replace it
+                    let best = self.high_scores.top(mode).next().cloned();
+
+                    display::display_sprint_results(
+                        &mut self.stdout,
+                        &self.keymap,
+                        &engine,
+                        best.as_ref(),
+                    );
+                }
+            }
+
+            self.finish_game(&engine, replay, reason);
+
+            if !self.wait_for_restart() {
+                return;
+            }
+
+            engine = new_engine(config);
+            replay = Replay::new(config, engine.seed);
+
+            // The time spent on the game over screen is not played back
+            self.timestep.reset();
+            self.draw(&engine, show_ghost);
+        }
+    }
+
+    /// Keeps the results and the recording of a game that came to an end
+    fn finish_game(&mut self, engine: &Engine, mut replay: Replay, reason: EndReason) {
+        let outcome = GameOutcome {
+            reason,
+            score: engine.score,
+            lines: engine.lines,
+            duration: engine.duration(),
+            seed: engine.seed,
+        };
+
+        self.last_outcome = Some(outcome);
+
+        let mode = replay.config.mode;
+        let high_score = HighScore {
+            mode,
+            score: outcome.score,
+            lines: outcome.lines,
+            duration: outcome.duration,
+            splits: engine.splits.clone(),
+        };
+
+        // Only a sprint that reached its goal has a time worth keeping
+        let kept = match mode {
+            GameMode::Marathon => outcome.score > 0,
+            GameMode::Sprint(_) => reason == EndReason::Finished,
+        };
+
+        // A score that cannot be saved is still shown until the game is closed
+        if kept && self.high_scores.insert(high_score).is_some() {
+            self.high_scores.save().ok();
+        }
+
+        if engine.frames > 0 {
+            replay.finish(engine.frames, outcome);
+            self.replays.push(replay);
+        }
+    }
+
+    /// Waits on the game over screen until the player chooses to restart or to go back to the
+    /// menu
+    ///
+    /// # Returns
+    ///
+    /// A boolean indicating if the player chose to restart
+    fn wait_for_restart(&mut self) -> bool {
+        loop {
+            for key in self.read_keys() {
+                match (self.keymap.action_for(key.key), key.state) {
+                    (Some(Action::Restart), KeyState::Press) => return true,
+                    (Some(Action::Quit), KeyState::Press) => return false,
+                    _ => {}
+                }
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Waits until the player presses or holds a key bound to an action of the menus
+    fn menu_action(&mut self) -> Action {
+        loop {
+            if let Some(action) = self.menu_actions.pop_front() {
+                return action;
+            }
+
+            for key in self.read_keys() {
+                if key.state == KeyState::Release {
+                    continue;
+                }
+
+                if let Some(action) = self.keymap.menu_action_for(key.key) {
+                    self.menu_actions.push_back(action);
+                }
+            }
+
+            if self.menu_actions.is_empty() {
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+
+    /// Reads the keys that came in from the terminal since the last call
+    fn read_keys(&mut self) -> Vec<KeyEvent> {
+        let now = Instant::now();
+        let mut keys = Vec::new();
+
+        while let Some(Ok(byte)) = self.stdin.next() {
+            keys.extend(self.keyboard.feed(byte, now));
+        }
+
+        keys.extend(self.keyboard.expire(now));
+
+        keys
+    }
+
+    /// Draws a game, timing how long drawing took
+    fn draw(&mut self, engine: &Engine, show_ghost: bool) {
+        let start = Instant::now();
+
+        let stats = self.config.stats.then_some(&self.timestep.stats);
+        display::display_screen(engine, &mut self.stdout, show_ghost, stats);
+
+        self.timestep.stats.record_render(start.elapsed());
+    }
+}
+
+/// Returns what can change on screen from one frame to the next without an event
+fn view_of(engine: &Engine) -> (PieceKind, i32, i32, i32, bool) {
+    let tetromino = &engine.current_tetromino;
+
+    (
+        tetromino.kind,
+        tetromino.x,
+        tetromino.y,
+        tetromino.rotation,
+        engine.hold_used,
+    )
+}
+
+/// Reads the game settings from the command line arguments
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<GameConfig, String> {
+    let mut config = GameConfig::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+
+        match arg.as_str() {
+            "--seed" => config.seed = Some(parse_number(&value()?, "seed")?),
+            "--randomizer" => config.ruleset.randomizer = value()?.parse()?,
+            "--spawn" => config.ruleset.spawn = value()?.parse()?,
+            "--infinite-hold" => config.ruleset.infinite_hold = true,
+            "--kicks-180" => config.ruleset.kicks_180 = value()?.parse()?,
+            "--no-ghost" => config.ghost = false,
+            "--stats" => config.stats = true,
+            "--board" => {
+                // The hidden rows are their own option, so they are kept whatever order the
+                // options come in
+                config.board = BoardSize {
+                    hidden_height: config.board.hidden_height,
+                    ..value()?.parse()?
+                }
+            }
+            "--hidden" => config.board.hidden_height = parse_number(&value()?, "hidden rows")?,
+            "--lock-delay" => config.ruleset.lock_delay = parse_number(&value()?, "lock delay")?,
+            "--lock-reset" => config.ruleset.lock_reset = value()?.parse()?,
+            "--gravity" => config.ruleset.gravity = value()?.parse()?,
+            "--scoring" => config.ruleset.scoring = value()?.parse()?,
+            "--level" => config.ruleset.start_level = parse_number(&value()?, "level")?,
+            "--das" => config.handling.das = parse_number(&value()?, "DAS")?,
+            "--arr" => config.handling.arr = parse_number(&value()?, "ARR")?,
+            "--dcd" => config.handling.dcd = parse_number(&value()?, "DCD")?,
+            "--sdf" => {
+                let factor = value()?;
+                config.handling.soft_drop_factor = match factor.parse() {
+                    Ok(factor) if factor > 0 => factor,
+                    _ => return Err(format!("Invalid soft drop factor '{}'", factor)),
+                };
+            }
+            "--lines-per-level" => {
+                let lines = value()?;
+                config.ruleset.lines_per_level = match lines.parse() {
+                    Ok(lines) if lines > 0 => lines,
+                    _ => return Err(format!("Invalid lines per level '{}'", lines)),
+                };
+            }
+            "--preview" => {
+                let length = value()?;
+                config.ruleset.preview_length = match length.parse() {
+                    Ok(length) if length <= MAX_PREVIEW => length,
+                    _ => return Err(format!("Invalid preview length '{}'", length)),
+                };
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        }
+    }
+
+    config.board.validate()?;
+
+    Ok(config)
+}
+
+/// Reads a number given as the value of a command line argument
+fn parse_number<T: FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid {} '{}'", name, value))
+}
+
+/// Creates the engine for a new game, with a random seed unless one was given
+fn new_engine(config: GameConfig) -> Engine {
+    Engine::new(config, config.seed.unwrap_or_else(rand::random))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<GameConfig, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn board_size_keeps_the_hidden_rows_in_any_order() {
+        for args in [
+            ["--hidden", "4", "--board", "12x24"],
+            ["--board", "12x24", "--hidden", "4"],
+        ] {
+            let board = parse(&args).unwrap().board;
+
+            assert_eq!((board.width, board.visible_height), (12, 24));
+            assert_eq!(board.hidden_height, 4);
+        }
+    }
 }
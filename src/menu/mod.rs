@@ -0,0 +1,313 @@
+use crate::board::BoardSize;
+use crate::engine::{self, FRAME_RATE, MAX_PREVIEW};
+use crate::gravity::GravityCurve;
+use crate::randomizer::RandomizerKind;
+use crate::ruleset::{LockReset, SpawnRule};
+use crate::scoring::ScoringTable;
+use crate::tetromino::srs::Kicks180;
+use crate::{GameConfig, GameMode};
+
+const MAX_START_LEVEL: u32 = 30; // The NES gravity table stops changing past level 29
+const MAX_LINES_PER_LEVEL: u32 = 50;
+const MAX_LOCK_DELAY: u32 = 2000;
+const LOCK_DELAY_STEP: u32 = 50;
+const MAX_SHIFT_FRAMES: u32 = 30; // Longest DAS, ARR and DCD offered, in frames
+const MAX_SOFT_DROP_FACTOR: u32 = 40;
+
+// Board sizes offered in the settings, besides the one given on the command line
+const BOARD_PRESETS: [BoardSize; 3] = [
+    BoardSize {
+        width: 10,
+        visible_height: 38,
+        hidden_height: 2,
+        scale: 1,
+    },
+    BoardSize {
+        width: 10,
+        visible_height: 20,
+        hidden_height: 2,
+        scale: 1,
+    },
+    BoardSize::BIG,
+];
+
+/// Represents an entry of the main menu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuEntry {
+    /// Starts a game of the given mode
+    Play(GameMode),
+    Settings,
+    KeyBindings,
+    HighScores,
+    Replays,
+    Quit,
+}
+
+impl MenuEntry {
+    /// Returns every entry of the main menu, the game modes first
+    pub fn all() -> Vec<MenuEntry> {
+        GameMode::ALL
+            .into_iter()
+            .map(MenuEntry::Play)
+            .chain([
+                MenuEntry::Settings,
+                MenuEntry::KeyBindings,
+                MenuEntry::HighScores,
+                MenuEntry::Replays,
+                MenuEntry::Quit,
+            ])
+            .collect()
+    }
+
+    /// Returns the name the entry is shown with
+    pub fn title(self) -> String {
+        match self {
+            MenuEntry::Play(mode) => mode.title(),
+            MenuEntry::Settings => String::from("Settings"),
+            MenuEntry::KeyBindings => String::from("Key bindings"),
+            MenuEntry::HighScores => String::from("High scores"),
+            MenuEntry::Replays => String::from("Replays"),
+            MenuEntry::Quit => String::from("Quit"),
+        }
+    }
+}
+
+/// Represents the entry selected in a list the player moves through
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub index: usize,
+    len: usize,
+}
+
+impl Selection {
+    /// Creates the selection of a list with the given number of entries, the first one selected
+    pub fn new(len: usize) -> Selection {
+        Selection { index: 0, len }
+    }
+
+    /// Selects the previous entry, going around to the last one from the first
+    pub fn up(&mut self) {
+        if self.len > 0 {
+            self.index = (self.index + self.len - 1) % self.len;
+        }
+    }
+
+    /// Selects the next entry, going around to the first one from the last
+    pub fn down(&mut self) {
+        if self.len > 0 {
+            self.index = (self.index + 1) % self.len;
+        }
+    }
+}
+
+/// Represents a setting the player can change from the menu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    StartLevel,
+    LinesPerLevel,
+    Gravity,
+    Scoring,
+    Randomizer,
+    Spawn,
+    Kicks180,
+    Preview,
+    InfiniteHold,
+    LockDelay,
+    LockReset,
+    Board,
+    Ghost,
+    Das,
+    Arr,
+    Dcd,
+    SoftDropFactor,
+}
+
+impl Setting {
+    /// Every setting, in the order they are listed to the player
+    pub const ALL: [Setting; 17] = [
+        Setting::StartLevel,
+        Setting::LinesPerLevel,
+        Setting::Gravity,
+        Setting::Scoring,
+        Setting::Randomizer,
+        Setting::Spawn,
+        Setting::Kicks180,
+        Setting::Preview,
+        Setting::InfiniteHold,
+        Setting::LockDelay,
+        Setting::LockReset,
+        Setting::Board,
+        Setting::Ghost,
+        Setting::Das,
+        Setting::Arr,
+        Setting::Dcd,
+        Setting::SoftDropFactor,
+    ];
+
+    /// Returns the name the setting is shown with
+    pub fn title(self) -> &'static str {
+        match self {
+            Setting::StartLevel => "Start level",
+            Setting::LinesPerLevel => "Lines per level",
+            Setting::Gravity => "Gravity",
+            Setting::Scoring => "Scoring",
+            Setting::Randomizer => "Randomizer",
+            Setting::Spawn => "Spawn",
+            Setting::Kicks180 => "180 kicks",
+            Setting::Preview => "Preview",
+            Setting::InfiniteHold => "Infinite hold",
+            Setting::LockDelay => "Lock delay",
+            Setting::LockReset => "Lock reset",
+            Setting::Board => "Board",
+            Setting::Ghost => "Ghost piece",
+            Setting::Das => "DAS",
+            Setting::Arr => "ARR",
+            Setting::Dcd => "DCD",
+            Setting::SoftDropFactor => "Soft drop factor",
+        }
+    }
+
+    /// Returns the value of the setting in the given settings, as shown to the player
+    pub fn value(self, config: &GameConfig) -> String {
+        let ruleset = &config.ruleset;
+        let handling = &config.handling;
+
+        match self {
+            Setting::StartLevel => ruleset.start_level.to_string(),
+            Setting::LinesPerLevel => ruleset.lines_per_level.to_string(),
+            Setting::Gravity => ruleset.gravity.name().to_string(),
+            Setting::Scoring => ruleset.scoring.name().to_string(),
+            Setting::Randomizer => ruleset.randomizer.name().to_string(),
+            Setting::Spawn => ruleset.spawn.name().to_string(),
+            Setting::Kicks180 => ruleset.kicks_180.name().to_string(),
+            Setting::Preview => ruleset.preview_length.to_string(),
+            Setting::InfiniteHold => on_off(ruleset.infinite_hold),
+            Setting::LockDelay => format!("{} ms", ruleset.lock_delay),
+            Setting::LockReset => ruleset.lock_reset.name().to_string(),
+            Setting::Board => config.board.to_string(),
+            Setting::Ghost => on_off(config.ghost),
+            Setting::Das => format!("{} ms", handling.das),
+            Setting::Arr => format!("{} ms", handling.arr),
+            Setting::Dcd => format!("{} ms", handling.dcd),
+            Setting::SoftDropFactor => format!("{}x", handling.soft_drop_factor),
+        }
+    }
+
+    /// Changes the setting to its next or previous value
+    ///
+    /// Numbers stop at the ends of their range, while choices go around
+    pub fn change(self, config: &mut GameConfig, forward: bool) {
+        let ruleset = &mut config.ruleset;
+        let handling = &mut config.handling;
+
+        match self {
+            Setting::StartLevel => {
+                ruleset.start_level = step(ruleset.start_level, 1, 1, MAX_START_LEVEL, forward)
+            }
+            Setting::LinesPerLevel => {
+                ruleset.lines_per_level =
+                    step(ruleset.lines_per_level, 1, 1, MAX_LINES_PER_LEVEL, forward)
+            }
+            Setting::Gravity => {
+                ruleset.gravity = cycle(&GravityCurve::ALL, ruleset.gravity, forward)
+            }
+            Setting::Scoring => {
+                ruleset.scoring = cycle(&ScoringTable::ALL, ruleset.scoring, forward)
+            }
+            Setting::Randomizer => {
+                ruleset.randomizer = cycle(&RandomizerKind::ALL, ruleset.randomizer, forward)
+            }
+            Setting::Spawn => ruleset.spawn = cycle(&SpawnRule::ALL, ruleset.spawn, forward),
+            Setting::Kicks180 => {
+                ruleset.kicks_180 = cycle(&Kicks180::ALL, ruleset.kicks_180, forward)
+            }
+            Setting::Preview => {
+                ruleset.preview_length = step(
+                    ruleset.preview_length as u32,
+                    1,
+                    0,
+                    MAX_PREVIEW as u32,
+                    forward,
+                ) as usize
+            }
+            Setting::InfiniteHold => ruleset.infinite_hold = !ruleset.infinite_hold,
+            Setting::LockDelay => {
+                ruleset.lock_delay = step(
+                    ruleset.lock_delay,
+                    LOCK_DELAY_STEP,
+                    0,
+                    MAX_LOCK_DELAY,
+                    forward,
+                )
+            }
+            Setting::LockReset => {
+                ruleset.lock_reset = cycle(&LockReset::ALL, ruleset.lock_reset, forward)
+            }
+            Setting::Board => {
+                // A size given on the command line stays among the choices
+                let mut sizes = BOARD_PRESETS.to_vec();
+                if !sizes.contains(&config.board) {
+                    sizes.insert(0, config.board);
+                }
+
+                config.board = cycle(&sizes, config.board, forward);
+            }
+            Setting::Ghost => config.ghost = !config.ghost,
+            Setting::Das => handling.das = step_frames(handling.das, forward),
+            Setting::Arr => handling.arr = step_frames(handling.arr, forward),
+            Setting::Dcd => handling.dcd = step_frames(handling.dcd, forward),
+            Setting::SoftDropFactor => {
+                handling.soft_drop_factor = step(
+                    handling.soft_drop_factor,
+                    1,
+                    1,
+                    MAX_SOFT_DROP_FACTOR,
+                    forward,
+                )
+            }
+        }
+    }
+}
+
+/// Returns how a setting that is turned on or off is shown
+fn on_off(value: bool) -> String {
+    String::from(if value { "on" } else { "off" })
+}
+
+/// Returns the choice after or before the current one, going around at the ends
+fn cycle<T: Copy + PartialEq>(choices: &[T], current: T, forward: bool) -> T {
+    let index = choices
+        .iter()
+        .position(|&choice| choice == current)
+        .unwrap_or(0);
+
+    let next = if forward {
+        (index + 1) % choices.len()
+    } else {
+        (index + choices.len() - 1) % choices.len()
+    };
+
+    choices[next]
+}
+
+/// Returns the number after or before the current one, staying within the given range
+fn step(current: u32, by: u32, min: u32, max: u32, forward: bool) -> u32 {
+    let next = if forward {
+        current.saturating_add(by)
+    } else {
+        current.saturating_sub(by)
+    };
+
+    next.clamp(min, max)
+}
+
+/// Returns the time one frame longer or shorter than the given one, in milliseconds
+///
+/// The engine rounds handling times to whole frames, so stepping by frames makes every step
+/// change how the game plays
+fn step_frames(milliseconds: u32, forward: bool) -> u32 {
+    let frames = engine::frames_of(milliseconds);
+    let frames = step(frames, 1, 0, MAX_SHIFT_FRAMES, forward);
+
+    (frames * 1000 + FRAME_RATE / 2) / FRAME_RATE
+}
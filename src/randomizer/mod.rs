@@ -0,0 +1,241 @@
+use std::str::FromStr;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::tetromino::kind::PieceKind;
+
+/// Represents a generator of the sequence of pieces dealt to the player
+pub trait Randomizer {
+    /// Returns the next piece of the sequence
+    fn next_piece(&mut self) -> PieceKind;
+}
+
+/// Represents the randomizers a game can be played with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RandomizerKind {
+    /// Every piece is picked independently
+    Random,
+    /// The seven pieces are dealt in a shuffled bag
+    SevenBag,
+    /// Two copies of the seven pieces are dealt in a shuffled bag
+    FourteenBag,
+    /// A piece repeating the previous one is rerolled once, as in the NES version
+    Classic,
+    /// A piece found in the last four pieces is rolled again, up to four tries, as in TGM
+    Tgm,
+}
+
+impl RandomizerKind {
+    /// Every randomizer kind, in the order they are offered to the player
+    pub const ALL: [RandomizerKind; 5] = [
+        RandomizerKind::Random,
+        RandomizerKind::SevenBag,
+        RandomizerKind::FourteenBag,
+        RandomizerKind::Classic,
+        RandomizerKind::Tgm,
+    ];
+
+    /// Returns the name used to select the randomizer from the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            RandomizerKind::Random => "random",
+            RandomizerKind::SevenBag => "7-bag",
+            RandomizerKind::FourteenBag => "14-bag",
+            RandomizerKind::Classic => "classic",
+            RandomizerKind::Tgm => "tgm",
+        }
+    }
+}
+
+impl FromStr for RandomizerKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        RandomizerKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| format!("Unknown randomizer '{}'", name))
+    }
+}
+
+/// Creates the randomizer of the given kind
+///
+/// The pieces are drawn from ChaCha8, whose output is fixed for a given seed on every platform and
+/// version, so seeds shown to players and kept in replays keep dealing the same pieces
+///
+/// # Arguments
+///
+/// * `kind` - The kind of randomizer to create
+/// * `seed` - The seed the whole piece sequence is generated from
+pub fn create_randomizer(kind: RandomizerKind, seed: u64) -> Box<dyn Randomizer> {
+    let rng = ChaCha8Rng::seed_from_u64(seed);
+
+    match kind {
+        RandomizerKind::Random => Box::new(PureRandom { rng }),
+        RandomizerKind::SevenBag => Box::new(Bag::new(rng, 1)),
+        RandomizerKind::FourteenBag => Box::new(Bag::new(rng, 2)),
+        RandomizerKind::Classic => Box::new(Classic { rng, last: None }),
+        RandomizerKind::Tgm => Box::new(TgmHistory::new(rng)),
+    }
+}
+
+/// Picks every piece independently of the previous ones
+pub struct PureRandom {
+    rng: ChaCha8Rng,
+}
+
+impl Randomizer for PureRandom {
+    fn next_piece(&mut self) -> PieceKind {
+        *PieceKind::ALL.choose(&mut self.rng).unwrap()
+    }
+}
+
+/// Deals the pieces from a shuffled bag holding a number of copies of each piece, refilling it
+/// once it is empty
+pub struct Bag {
+    rng: ChaCha8Rng,
+    copies: usize,
+    bag: Vec<PieceKind>,
+}
+
+impl Bag {
+    pub fn new(rng: ChaCha8Rng, copies: usize) -> Bag {
+        Bag {
+            rng,
+            copies,
+            bag: Vec::new(),
+        }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next_piece(&mut self) -> PieceKind {
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend(PieceKind::ALL);
+            }
+
+            self.bag.shuffle(&mut self.rng);
+        }
+
+        self.bag.pop().unwrap()
+    }
+}
+
+/// Rolls an eight sided die where the eighth side, or repeating the last piece, rerolls
+/// once among the seven pieces, as in the NES version
+pub struct Classic {
+    rng: ChaCha8Rng,
+    last: Option<PieceKind>,
+}
+
+impl Randomizer for Classic {
+    fn next_piece(&mut self) -> PieceKind {
+        let roll = self.rng.gen_range(0..=PieceKind::ALL.len());
+
+        let piece = match PieceKind::ALL.get(roll) {
+            Some(&piece) if Some(piece) != self.last => piece,
+            _ => *PieceKind::ALL.choose(&mut self.rng).unwrap(),
+        };
+
+        self.last = Some(piece);
+        piece
+    }
+}
+
+const TGM_ROLLS: usize = 4; // Tries at finding a piece that is not in the history
+
+/// Keeps a history of the last four pieces and rerolls pieces found in it, as in TGM
+pub struct TgmHistory {
+    rng: ChaCha8Rng,
+    history: [PieceKind; 4],
+    first: bool,
+}
+
+impl TgmHistory {
+    pub fn new(rng: ChaCha8Rng) -> TgmHistory {
+        TgmHistory {
+            rng,
+            history: [PieceKind::Z; 4],
+            first: true,
+        }
+    }
+}
+
+impl Randomizer for TgmHistory {
+    fn next_piece(&mut self) -> PieceKind {
+        let piece = if self.first {
+            // The first piece is never one that could leave an overhang
+            self.first = false;
+            *[PieceKind::I, PieceKind::T, PieceKind::J, PieceKind::L]
+                .choose(&mut self.rng)
+                .unwrap()
+        } else {
+            let mut piece = *PieceKind::ALL.choose(&mut self.rng).unwrap();
+
+            for _ in 1..TGM_ROLLS {
+                if !self.history.contains(&piece) {
+                    break;
+                }
+
+                piece = *PieceKind::ALL.choose(&mut self.rng).unwrap();
+            }
+
+            piece
+        };
+
+        self.history.rotate_right(1);
+        self.history[0] = piece;
+
+        piece
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PieceKind::*;
+
+    const SEED: u64 = 42;
+
+    /// The first pieces every randomizer deals from SEED, which must never change
+    const SEQUENCES: [(RandomizerKind, [PieceKind; 14]); 5] = [
+        (
+            RandomizerKind::Random,
+            [O, Z, O, L, J, T, Z, J, T, Z, O, T, T, O],
+        ),
+        (
+            RandomizerKind::SevenBag,
+            [O, Z, T, L, J, S, I, O, Z, J, T, L, S, I],
+        ),
+        (
+            RandomizerKind::FourteenBag,
+            [S, J, T, O, L, O, T, S, Z, J, I, Z, L, I],
+        ),
+        (
+            RandomizerKind::Classic,
+            [J, S, J, T, O, T, L, J, Z, S, L, J, O, O],
+        ),
+        (
+            RandomizerKind::Tgm,
+            [L, T, J, O, J, Z, S, T, L, J, Z, O, I, S],
+        ),
+    ];
+
+    #[test]
+    fn seeded_sequences_never_change() {
+        for (kind, expected) in SEQUENCES {
+            let mut randomizer = create_randomizer(kind, SEED);
+            let pieces: Vec<PieceKind> = expected.iter().map(|_| randomizer.next_piece()).collect();
+
+            assert_eq!(
+                pieces,
+                expected,
+                "{} dealt a different sequence",
+                kind.name()
+            );
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::engine::{Engine, Event, Input};
+use crate::{GameConfig, GameMode, GameOutcome};
+
+const SCORES_FILE: &str = "rust-tetris/scores.toml"; // Inside the user's data directory
+const MAX_HIGH_SCORES: usize = 10; // Results kept for every mode
+const MAX_REPLAYS: usize = 10; // Games of the session kept to be watched again
+
+/// Represents the result of a game kept in the high score table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HighScore {
+    pub mode: GameMode,
+    pub score: u32,
+    pub lines: u32,
+    pub duration: Duration,
+    pub splits: Vec<Duration>, // Time every tenth line was cleared at
+}
+
+impl HighScore {
+    /// Orders the results of a mode from the best to the worst, the highest score winning a
+    /// marathon and the fastest time winning a sprint
+    fn compare(&self, other: &HighScore) -> Ordering {
+        match self.mode {
+            GameMode::Marathon => other
+                .score
+                .cmp(&self.score)
+                .then(self.duration.cmp(&other.duration)),
+            GameMode::Sprint(_) => self
+                .duration
+                .cmp(&other.duration)
+                .then(other.score.cmp(&self.score)),
+        }
+    }
+}
+
+/// Represents the best results of every mode, kept between sessions in the user's data directory
+#[derive(Clone, Debug, Default)]
+pub struct HighScores {
+    scores: Vec<HighScore>, // Sorted from the best to the worst within every mode
+}
+
+impl HighScores {
+    /// Returns where the high score file is kept, inside $XDG_DATA_HOME or ~/.local/share
+    pub fn path() -> Option<PathBuf> {
+        let data_dir = std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })?;
+
+        Some(data_dir.join(SCORES_FILE))
+    }
+
+    /// Loads the high score file, starting with an empty table when there is no file
+    pub fn load() -> Result<HighScores, String> {
+        let Some(path) = HighScores::path() else {
+            return Ok(HighScores::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => HighScores::from_toml(&text)
+                .map_err(|error| format!("{}: {}", path.display(), error)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(HighScores::default()),
+            Err(error) => Err(format!("{}: {}", path.display(), error)),
+        }
+    }
+
+    /// Writes the high score file, creating its directory if needed
+    pub fn save(&self) -> Result<(), String> {
+        let path = HighScores::path().ok_or("No data directory to keep high scores in")?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|error| format!("{}: {}", dir.display(), error))?;
+        }
+
+        fs::write(&path, self.to_toml()).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    /// Reads a high score table written as one array of tables for every mode
+    ///
+    /// ```toml
+    /// [[marathon]]
+    /// score = 12000
+    /// lines = 42
+    /// time = 215000 # Milliseconds
+    ///
+    /// [[sprint-40]]
+    /// score = 5400
+    /// lines = 40
+    /// time = 62350
+    /// splits = [14200, 29800, 46100, 62350]
+    /// ```
+    pub fn from_toml(text: &str) -> Result<HighScores, String> {
+        let table: toml::Table = text.parse().map_err(|error| format!("{}", error))?;
+        let mut high_scores = HighScores::default();
+
+        for (name, value) in table {
+            let mode = name.parse()?;
+            let entries = value
+                .as_array()
+                .ok_or_else(|| format!("The scores of '{}' must be a list", name))?;
+
+            for entry in entries {
+                let entry = entry
+                    .as_table()
+                    .ok_or_else(|| format!("The scores of '{}' must be tables", name))?;
+
+                // Missing fields are read as 0, so older files still load
+                let field = |key: &str| -> Result<u64, String> {
+                    match entry.get(key) {
+                        Some(value) => value
+                            .as_integer()
+                            .and_then(|value| u64::try_from(value).ok())
+                            .ok_or_else(|| format!("Invalid {} in the scores of '{}'", key, name)),
+                        None => Ok(0),
+                    }
+                };
+
+                let splits = match entry.get("splits") {
+                    Some(value) => value
+                        .as_array()
+                        .and_then(|splits| {
+                            splits
+                                .iter()
+                                .map(|split| {
+                                    split
+                                        .as_integer()
+                                        .and_then(|split| u64::try_from(split).ok())
+                                })
+                                .map(|split| split.map(Duration::from_millis))
+                                .collect::<Option<Vec<Duration>>>()
+                        })
+                        .ok_or_else(|| format!("Invalid splits in the scores of '{}'", name))?,
+                    None => Vec::new(),
+                };
+
+                high_scores.insert(HighScore {
+                    mode,
+                    score: field("score")? as u32,
+                    lines: field("lines")? as u32,
+                    duration: Duration::from_millis(field("time")?),
+                    splits,
+                });
+            }
+        }
+
+        Ok(high_scores)
+    }
+
+    /// Writes the high score table in the format read back by from_toml
+    pub fn to_toml(&self) -> String {
+        let mut text = String::new();
+
+        for score in &self.scores {
+            text += &format!(
+                "[[{}]]\nscore = {}\nlines = {}\ntime = {}\n",
+                score.mode.name(),
+                score.score,
+                score.lines,
+                score.duration.as_millis()
+            );
+
+            if !score.splits.is_empty() {
+                let splits: Vec<String> = score
+                    .splits
+                    .iter()
+                    .map(|split| split.as_millis().to_string())
+                    .collect();
+
+                text += &format!("splits = [{}]\n", splits.join(", "));
+            }
+
+            text += "\n";
+        }
+
+        text
+    }
+
+    /// Adds a result to the table, if it is among the best of its mode
+    ///
+    /// # Returns
+    ///
+    /// The rank of the result within its mode, starting at 0, if it was kept
+    pub fn insert(&mut self, score: HighScore) -> Option<usize> {
+        let mode = score.mode;
+        let rank = self
+            .top(mode)
+            .position(|other| score.compare(other) == Ordering::Less)
+            .unwrap_or_else(|| self.top(mode).count());
+
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        self.scores.push(score);
+        self.scores
+            .sort_by(|a, b| a.mode.name().cmp(&b.mode.name()).then(a.compare(b)));
+
+        // Only the best results of the mode are kept
+        let mut kept = 0;
+        self.scores.retain(|other| {
+            kept += (other.mode == mode) as usize;
+            other.mode != mode || kept <= MAX_HIGH_SCORES
+        });
+
+        Some(rank)
+    }
+
+    /// Returns the results of a mode, from the best to the worst
+    pub fn top(&self, mode: GameMode) -> impl Iterator<Item = &HighScore> {
+        self.scores.iter().filter(move |score| score.mode == mode)
+    }
+}
+
+/// Represents a key of the game changing state, on the frame it happened
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayInput {
+    pub frame: u64, // Frames the engine had simulated when the input was given
+    pub input: Input,
+    pub pressed: bool, // The key was pressed, or released otherwise
+}
+
+/// Represents a game recorded to be watched again
+///
+/// The engine plays out the same game from the same settings, seed and inputs, so nothing else
+/// needs to be recorded
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub config: GameConfig,
+    pub seed: u64,
+    pub inputs: Vec<ReplayInput>,
+    pub frames: u64,                  // Frames the game lasted
+    pub outcome: Option<GameOutcome>, // Known once the game is over
+}
+
+impl Replay {
+    /// Starts recording a game
+    pub fn new(config: GameConfig, seed: u64) -> Replay {
+        Replay {
+            config,
+            seed,
+            inputs: Vec::new(),
+            frames: 0,
+            outcome: None,
+        }
+    }
+
+    /// Records an input given to the engine
+    pub fn record(&mut self, frame: u64, input: Input, pressed: bool) {
+        self.inputs.push(ReplayInput {
+            frame,
+            input,
+            pressed,
+        });
+    }
+
+    /// Stops recording the game with its results
+    pub fn finish(&mut self, frames: u64, outcome: GameOutcome) {
+        self.frames = frames;
+        self.outcome = Some(outcome);
+    }
+}
+
+/// Plays a recorded game back, giving the engine the recorded inputs on the frames they were given
+pub struct Playback<'a> {
+    pub engine: Engine,
+    replay: &'a Replay,
+    next_input: usize, // The first recorded input not given to the engine yet
+}
+
+impl<'a> Playback<'a> {
+    /// Starts playing a recorded game from its first frame
+    pub fn new(replay: &'a Replay) -> Playback<'a> {
+        Playback {
+            engine: Engine::new(replay.config, replay.seed),
+            replay,
+            next_input: 0,
+        }
+    }
+
+    /// Plays the given number of frames of the game
+    ///
+    /// # Returns
+    ///
+    /// The events of the game during those frames
+    pub fn advance(&mut self, frames: u32) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        for _ in 0..frames {
+            // Inputs are given before the frame is simulated, as they were while playing, and the
+            // last ones can end the game without another frame
+            while let Some(input) = self
+                .replay
+                .inputs
+                .get(self.next_input)
+                .filter(|input| input.frame <= self.engine.frames)
+            {
+                if input.pressed {
+                    events.extend(self.engine.press(input.input));
+                } else {
+                    self.engine.release(input.input);
+                }
+
+                self.next_input += 1;
+            }
+
+            if self.is_finished() {
+                break;
+            }
+
+            events.extend(self.engine.tick(1));
+        }
+
+        events
+    }
+
+    /// Checks if the recorded game has been played to its end
+    ///
+    /// Inputs given on the last frame can still be waiting, so the game only ends once they are
+    pub fn is_finished(&self) -> bool {
+        self.engine.game_over.is_some()
+            || (self.engine.frames >= self.replay.frames
+                && self.next_input == self.replay.inputs.len())
+    }
+}
+
+/// Represents the games played during the session, kept to be watched again
+#[derive(Clone, Debug, Default)]
+pub struct Replays {
+    replays: VecDeque<Replay>, // From the most recent to the oldest
+}
+
+impl Replays {
+    /// Keeps the recording of a finished game, forgetting the oldest one when there are too many
+    pub fn push(&mut self, replay: Replay) {
+        self.replays.push_front(replay);
+        self.replays.truncate(MAX_REPLAYS);
+    }
+
+    /// Returns the recorded games, from the most recent to the oldest
+    pub fn iter(&self) -> impl Iterator<Item = &Replay> {
+        self.replays.iter()
+    }
+
+    /// Returns the recorded game at the given position, the most recent being the first
+    pub fn get(&self, index: usize) -> Option<&Replay> {
+        self.replays.get(index)
+    }
+
+    /// Returns the number of recorded games
+    pub fn len(&self) -> usize {
+        self.replays.len()
+    }
+
+    /// Checks if no game was recorded
+    pub fn is_empty(&self) -> bool {
+        self.replays.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_plays_inputs_given_on_the_last_frame() {
+        let config = GameConfig::default();
+        let mut engine = Engine::new(config, 7);
+        let mut replay = Replay::new(config, engine.seed);
+
+        // Hard dropping every frame tops out the stack on an input, with no frame after it
+        while engine.game_over.is_none() {
+            engine.tick(1);
+            replay.record(engine.frames, Input::HardDrop, true);
+            engine.press(Input::HardDrop);
+        }
+
+        replay.finish(
+            engine.frames,
+            GameOutcome {
+                reason: engine.game_over.unwrap(),
+                score: engine.score,
+                lines: engine.lines,
+                duration: engine.duration(),
+                seed: engine.seed,
+            },
+        );
+
+        let mut playback = Playback::new(&replay);
+        while !playback.is_finished() {
+            playback.advance(1);
+        }
+
+        assert_eq!(playback.engine.game_over, engine.game_over);
+        assert_eq!(playback.engine.pieces, engine.pieces);
+        assert_eq!(playback.engine.score, engine.score);
+    }
+}
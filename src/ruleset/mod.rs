@@ -0,0 +1,110 @@
+use std::str::FromStr;
+
+use crate::gravity::GravityCurve;
+use crate::randomizer::RandomizerKind;
+use crate::scoring::ScoringTable;
+use crate::tetromino::srs::Kicks180;
+
+/// Represents the rules the game is played with
+#[derive(Clone, Copy, Debug)]
+pub struct Ruleset {
+    pub kicks_180: Kicks180, // The wall kick table used by 180 degree rotations
+    pub randomizer: RandomizerKind,
+    pub spawn: SpawnRule,
+    pub infinite_hold: bool, // Lets the player hold more than once before the tetromino locks
+    pub preview_length: usize, // Number of upcoming pieces shown, from 0 to 6
+    pub lock_delay: u32,     // Milliseconds a landed tetromino waits before locking
+    pub lock_reset: LockReset,
+    pub gravity: GravityCurve,
+    pub start_level: u32,
+    pub lines_per_level: u32, // Lines to clear to go up one level
+    pub scoring: ScoringTable,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset {
+            kicks_180: Kicks180::Tetrio,
+            randomizer: RandomizerKind::SevenBag,
+            spawn: SpawnRule::Guideline,
+            infinite_hold: false,
+            preview_length: 5,
+            lock_delay: 500,
+            lock_reset: LockReset::Move,
+            gravity: GravityCurve::Guideline,
+            start_level: 1,
+            lines_per_level: 10,
+            scoring: ScoringTable::Guideline,
+        }
+    }
+}
+
+/// Represents where and in which orientation new tetrominoes appear
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpawnRule {
+    /// Flat side down in the hidden rows, dropping into view right away when there is room
+    Guideline,
+    /// Flat side up in the first visible row, as in the NES version
+    Classic,
+}
+
+impl SpawnRule {
+    /// Every spawn rule, in the order they are offered to the player
+    pub const ALL: [SpawnRule; 2] = [SpawnRule::Guideline, SpawnRule::Classic];
+
+    /// Returns the name used to select the spawn rule from the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            SpawnRule::Guideline => "guideline",
+            SpawnRule::Classic => "classic",
+        }
+    }
+}
+
+impl FromStr for SpawnRule {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        SpawnRule::ALL
+            .into_iter()
+            .find(|rule| rule.name() == name)
+            .ok_or_else(|| format!("Unknown spawn rule '{}'", name))
+    }
+}
+
+/// Represents what gives a landed tetromino more time before it locks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockReset {
+    /// Every move or rotation restarts the lock delay, without limit
+    Infinite,
+    /// Every move or rotation restarts the lock delay, up to 15 times until the tetromino falls
+    /// below the lowest row it has reached
+    Move,
+    /// Only falling below the lowest row the tetromino has reached restarts the lock delay
+    Step,
+}
+
+impl LockReset {
+    /// Every lock reset rule, in the order they are offered to the player
+    pub const ALL: [LockReset; 3] = [LockReset::Infinite, LockReset::Move, LockReset::Step];
+
+    /// Returns the name used to select the lock reset rule from the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            LockReset::Infinite => "infinite",
+            LockReset::Move => "move",
+            LockReset::Step => "step",
+        }
+    }
+}
+
+impl FromStr for LockReset {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        LockReset::ALL
+            .into_iter()
+            .find(|reset| reset.name() == name)
+            .ok_or_else(|| format!("Unknown lock reset '{}'", name))
+    }
+}
@@ -0,0 +1,305 @@
+use std::str::FromStr;
+
+use crate::board::Board;
+use crate::tetromino::kind::PieceKind;
+use crate::tetromino::tetromino::Tetromino;
+
+const T_SPIN_UPGRADE_KICK: usize = 4; // The last SRS kick of a quarter turn, which always makes a full T-spin
+
+/// Represents the point tables a game can be scored with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoringTable {
+    /// Guideline points scaled by level, with T-spins, combos, back-to-back and perfect clears
+    Guideline,
+    /// The line clear points of the NES version, scaled by level
+    Nes,
+    /// The TGM formula, where combos multiply the points and perfect clears quadruple them
+    Tgm,
+}
+
+impl ScoringTable {
+    /// Every scoring table, in the order they are offered to the player
+    pub const ALL: [ScoringTable; 3] = [
+        ScoringTable::Guideline,
+        ScoringTable::Nes,
+        ScoringTable::Tgm,
+    ];
+
+    /// Returns the name used to select the scoring table from the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            ScoringTable::Guideline => "guideline",
+            ScoringTable::Nes => "nes",
+            ScoringTable::Tgm => "tgm",
+        }
+    }
+}
+
+impl FromStr for ScoringTable {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        ScoringTable::ALL
+            .into_iter()
+            .find(|table| table.name() == name)
+            .ok_or_else(|| format!("Unknown scoring table '{}'", name))
+    }
+}
+
+/// Represents whether a tetromino locked as a T-spin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TSpin {
+    None,
+    /// Only one of the corners the T points at is filled
+    Mini,
+    /// Both corners the T points at are filled, or the tetromino got in with the last kick
+    Full,
+}
+
+/// Represents how a tetromino that locked was scored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockScore {
+    pub lines: u32, // 1 to 4 for a single, double, triple or tetris
+    pub t_spin: TSpin,
+    pub combo: Option<u32>, // Line clears in a row after the first one, None when no line was cleared
+    pub back_to_back: bool, // The clear followed another tetris or T-spin clear
+    pub perfect_clear: bool, // The clear left the playfield empty
+    pub points: u32,
+}
+
+/// Keeps the chains that carry from one lock to the next and awards the points of every lock
+pub struct Scoring {
+    table: ScoringTable,
+    combo: Option<u32>,
+    back_to_back: bool, // The last line clear was a tetris or a T-spin clear
+    tgm_combo: u32,     // The multiplier the TGM formula builds up over consecutive clears
+}
+
+impl Scoring {
+    pub fn new(table: ScoringTable) -> Scoring {
+        Scoring {
+            table,
+            combo: None,
+            back_to_back: false,
+            tgm_combo: 1,
+        }
+    }
+
+    /// Classifies a lock, moves the combo and back-to-back chains forward and awards its points
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The number of lines the tetromino cleared
+    /// * `t_spin` - Whether the tetromino locked as a T-spin
+    /// * `perfect_clear` - Whether the clear left the playfield empty
+    /// * `level` - The level the tetromino locked on
+    pub fn score_lock(
+        &mut self,
+        lines: u32,
+        t_spin: TSpin,
+        perfect_clear: bool,
+        level: u32,
+    ) -> LockScore {
+        let difficult = lines == 4 || (lines > 0 && t_spin != TSpin::None);
+        let back_to_back = difficult && self.back_to_back;
+
+        if lines > 0 {
+            self.combo = Some(self.combo.map_or(0, |combo| combo + 1));
+            self.back_to_back = difficult;
+        } else {
+            self.combo = None;
+        }
+
+        let points = match self.table {
+            ScoringTable::Guideline => guideline_points(
+                lines,
+                t_spin,
+                perfect_clear,
+                back_to_back,
+                self.combo,
+                level,
+            ),
+            ScoringTable::Nes => nes_points(lines, level),
+            ScoringTable::Tgm => self.tgm_points(lines, perfect_clear, level),
+        };
+
+        LockScore {
+            lines,
+            t_spin,
+            combo: self.combo,
+            back_to_back,
+            perfect_clear,
+            points,
+        }
+    }
+
+    /// Follows the TGM formula, ceil((level + lines) / 4) * lines * combo * bravo, where the combo
+    /// grows by 2 * lines - 2 with every clear in a row and bravo is 4 for a perfect clear
+    fn tgm_points(&mut self, lines: u32, perfect_clear: bool, level: u32) -> u32 {
+        if lines == 0 {
+            self.tgm_combo = 1;
+            return 0;
+        }
+
+        self.tgm_combo += 2 * lines - 2;
+        let bravo = if perfect_clear { 4 } else { 1 };
+
+        (level + lines).div_ceil(4) * lines * self.tgm_combo * bravo
+    }
+}
+
+fn guideline_points(
+    lines: u32,
+    t_spin: TSpin,
+    perfect_clear: bool,
+    back_to_back: bool,
+    combo: Option<u32>,
+    level: u32,
+) -> u32 {
+    let level = level.max(1);
+
+    let base = match (t_spin, lines) {
+        (TSpin::None, 0) => 0,
+        (TSpin::None, 1) => 100,
+        (TSpin::None, 2) => 300,
+        (TSpin::None, 3) => 500,
+        (TSpin::None, _) => 800,
+        (TSpin::Mini, 0) => 100,
+        (TSpin::Mini, 1) => 200,
+        (TSpin::Mini, _) => 400,
+        (TSpin::Full, 0) => 400,
+        (TSpin::Full, 1) => 800,
+        (TSpin::Full, 2) => 1200,
+        (TSpin::Full, _) => 1600,
+    };
+
+    // Back-to-back clears are worth half as much again
+    let base = if back_to_back { base * 3 / 2 } else { base };
+
+    let combo_bonus = 50 * combo.unwrap_or(0);
+
+    let perfect_clear_bonus = match (perfect_clear, lines) {
+        (false, _) | (true, 0) => 0,
+        (true, 1) => 800,
+        (true, 2) => 1200,
+        (true, 3) => 1800,
+        (true, _) if back_to_back => 3200,
+        (true, _) => 2000,
+    };
+
+    (base + combo_bonus + perfect_clear_bonus) * level
+}
+
+fn nes_points(lines: u32, level: u32) -> u32 {
+    let base = match lines {
+        0 => 0,
+        1 => 40,
+        2 => 100,
+        3 => 300,
+        _ => 1200,
+    };
+
+    base * (level + 1)
+}
+
+/// Checks if a T tetromino that is about to lock got in with a rotation, following the
+/// 3-corner rule
+///
+/// At least three of the four corners around the center of the T have to be filled, the walls
+/// and the floor counting as filled. Both corners the T points at make a full T-spin, only one
+/// of them makes a mini, unless a quarter turn used its last kick. The kicks of 180 degree
+/// rotations are plain shifts and never upgrade a mini
+///
+/// # Arguments
+///
+/// * `tetromino` - The tetromino about to lock
+/// * `board` - The board the tetromino is played on
+pub fn t_spin(tetromino: &Tetromino, board: &Board) -> TSpin {
+    let Some((rotation, kick)) = tetromino.last_kick else {
+        return TSpin::None;
+    };
+
+    if tetromino.kind != PieceKind::T {
+        return TSpin::None;
+    }
+
+    let filled = |x_offset: i32, y_offset: i32| {
+        board.is_occupied(tetromino.x + x_offset, tetromino.y + y_offset)
+    };
+
+    // The corners of the 3x3 box, starting with the two the T points at
+    let corners = match tetromino.rotation {
+        0 => [(0, 0), (2, 0), (0, 2), (2, 2)],
+        90 => [(2, 0), (2, 2), (0, 0), (0, 2)],
+        180 => [(0, 2), (2, 2), (0, 0), (2, 0)],
+        _ => [(0, 0), (0, 2), (2, 0), (2, 2)],
+    }
+    .map(|(x, y)| filled(x, y));
+
+    let front = corners[..2].iter().filter(|&&corner| corner).count();
+    let total = corners.iter().filter(|&&corner| corner).count();
+
+    if total < 3 {
+        TSpin::None
+    } else if front == 2 || (rotation.abs() == 90 && kick == T_SPIN_UPGRADE_KICK) {
+        TSpin::Full
+    } else {
+        TSpin::Mini
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BoardSize;
+
+    /// Returns a board with a T pointing right against the left wall, in a slot where only one of
+    /// the corners it points at is filled
+    fn mini_slot() -> (Board, Tetromino) {
+        let size = BoardSize::default();
+        let mut board = Board::new(size);
+        let bottom = size.height() as i32 - 1;
+
+        board.place(&Tetromino::new(PieceKind::I, 2, bottom - 1));
+
+        let mut tetromino = Tetromino::new(PieceKind::T, 0, bottom - 2);
+        tetromino.rotation = 90;
+
+        (board, tetromino)
+    }
+
+    #[test]
+    fn last_kick_of_a_quarter_turn_upgrades_a_mini() {
+        let (board, mut tetromino) = mini_slot();
+
+        tetromino.last_kick = Some((90, 0));
+        assert_eq!(t_spin(&tetromino, &board), TSpin::Mini);
+
+        tetromino.last_kick = Some((90, T_SPIN_UPGRADE_KICK));
+        assert_eq!(t_spin(&tetromino, &board), TSpin::Full);
+
+        tetromino.last_kick = Some((-90, T_SPIN_UPGRADE_KICK));
+        assert_eq!(t_spin(&tetromino, &board), TSpin::Full);
+    }
+
+    #[test]
+    fn tgm_points_follow_the_tgm_formula() {
+        let mut scoring = Scoring::new(ScoringTable::Tgm);
+
+        // ceil((0 + 4) / 4) * 4 lines * combo (1 + 6)
+        assert_eq!(scoring.score_lock(4, TSpin::None, false, 0).points, 28);
+        // ceil((5 + 2) / 4) * 2 lines * combo (7 + 2)
+        assert_eq!(scoring.score_lock(2, TSpin::None, false, 5).points, 36);
+        // Locking without a clear breaks the combo, and a perfect clear is worth 4 times as much
+        assert_eq!(scoring.score_lock(0, TSpin::None, false, 5).points, 0);
+        assert_eq!(scoring.score_lock(1, TSpin::None, true, 5).points, 8);
+    }
+
+    #[test]
+    fn kicks_of_a_180_rotation_never_upgrade_a_mini() {
+        let (board, mut tetromino) = mini_slot();
+
+        tetromino.last_kick = Some((180, T_SPIN_UPGRADE_KICK));
+        assert_eq!(t_spin(&tetromino, &board), TSpin::Mini);
+    }
+}
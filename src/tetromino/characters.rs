@@ -1,31 +1,6 @@
 /* Tetromino */
-/// Represents a Tetromino character '[ ]' with its x and y position (Simulates a pixel)
-#[derive(Copy, Clone)]
-pub struct TetrominoCharacter {
-    pub x: i32,
-    pub y: i32,
-    pub value: &'static str, // Use &'static str for string literals
-}
+/// The text drawn for every cell of a tetromino
+pub const BLOCK: &str = "[ ]";
 
-impl TetrominoCharacter {
-    pub fn new(x_pos: i32, y_pos: i32, value: &'static str) -> TetrominoCharacter {
-        TetrominoCharacter {
-            x: x_pos,
-            y: y_pos,
-            value,
-        }
-    }
-
-    pub fn default() -> Self {
-        TetrominoCharacter {
-            x: 0,
-            y: 0,
-            value: "", // Default value, replace with actual default if needed
-        }
-    }
-
-    pub fn move_character(&mut self, x_units: i32, y_units: i32) {
-        self.x += x_units;
-        self.y += y_units;
-    }
-}
+/// The text drawn for every cell of the ghost tetromino, where the current tetromino would land
+pub const GHOST: &str = "[.]";
@@ -0,0 +1,89 @@
+/// Represents the seven standard tetrominoes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceKind {
+    I,
+    O,
+    T,
+    S,
+    Z,
+    J,
+    L,
+}
+
+impl PieceKind {
+    /// Every piece kind, in the order used by the randomizer
+    pub const ALL: [PieceKind; 7] = [
+        PieceKind::I,
+        PieceKind::O,
+        PieceKind::T,
+        PieceKind::S,
+        PieceKind::Z,
+        PieceKind::J,
+        PieceKind::L,
+    ];
+
+    /// Returns the width and height of the bounding box the piece rotates in
+    pub fn box_size(self) -> i32 {
+        match self {
+            PieceKind::I => 4,
+            PieceKind::O => 2,
+            _ => 3,
+        }
+    }
+
+    /// Returns the cells of the piece for the given rotation state, following the Super Rotation System
+    ///
+    /// Every rotation state is laid out inside the same bounding box (4x4 for I, 2x2 for O and 3x3
+    /// for the rest), so rotating around the box keeps the piece turning around its pivot
+    ///
+    /// # Arguments
+    ///
+    /// * `rotation` - The rotation in degrees (0, 90, 180 or 270), 0 being the spawn state
+    ///
+    /// # Returns
+    ///
+    /// A list of (x, y) offsets of the piece's cells from the top left of its bounding box
+    pub fn cells(self, rotation: i32) -> &'static [(i32, i32)] {
+        let state = (rotation.rem_euclid(360) / 90) as usize;
+
+        match self {
+            PieceKind::I => [
+                &[(0, 1), (1, 1), (2, 1), (3, 1)][..],
+                &[(2, 0), (2, 1), (2, 2), (2, 3)],
+                &[(0, 2), (1, 2), (2, 2), (3, 2)],
+                &[(1, 0), (1, 1), (1, 2), (1, 3)],
+            ][state],
+            PieceKind::O => &[(0, 0), (1, 0), (0, 1), (1, 1)],
+            PieceKind::T => [
+                &[(1, 0), (0, 1), (1, 1), (2, 1)][..],
+                &[(1, 0), (1, 1), (2, 1), (1, 2)],
+                &[(0, 1), (1, 1), (2, 1), (1, 2)],
+                &[(1, 0), (0, 1), (1, 1), (1, 2)],
+            ][state],
+            PieceKind::S => [
+                &[(1, 0), (2, 0), (0, 1), (1, 1)][..],
+                &[(1, 0), (1, 1), (2, 1), (2, 2)],
+                &[(1, 1), (2, 1), (0, 2), (1, 2)],
+                &[(0, 0), (0, 1), (1, 1), (1, 2)],
+            ][state],
+            PieceKind::Z => [
+                &[(0, 0), (1, 0), (1, 1), (2, 1)][..],
+                &[(2, 0), (1, 1), (2, 1), (1, 2)],
+                &[(0, 1), (1, 1), (1, 2), (2, 2)],
+                &[(1, 0), (0, 1), (1, 1), (0, 2)],
+            ][state],
+            PieceKind::J => [
+                &[(0, 0), (0, 1), (1, 1), (2, 1)][..],
+                &[(1, 0), (2, 0), (1, 1), (1, 2)],
+                &[(0, 1), (1, 1), (2, 1), (2, 2)],
+                &[(1, 0), (1, 1), (0, 2), (1, 2)],
+            ][state],
+            PieceKind::L => [
+                &[(2, 0), (0, 1), (1, 1), (2, 1)][..],
+                &[(1, 0), (1, 1), (1, 2), (2, 2)],
+                &[(0, 1), (1, 1), (2, 1), (0, 2)],
+                &[(0, 0), (1, 0), (1, 1), (1, 2)],
+            ][state],
+        }
+    }
+}
@@ -1,66 +1,63 @@
 pub mod characters;
-pub mod line;
+pub mod kind;
+pub mod srs;
+#[allow(clippy::module_inception)]
 pub mod tetromino;
 
-use self::line::Line;
+use self::kind::PieceKind;
 use self::tetromino::Tetromino;
-use rand::Rng;
+use crate::board::Board;
+use crate::ruleset::SpawnRule;
 
-const WIDTH: usize = 12; // 2 more to account for the borders
+/// Creates a tetromino of the given kind at the spawn position of the spawn rule
+///
+/// Guideline tetrominoes spawn flat side down, centered and rounded to the left, in the hidden
+/// rows right above the visible field. Classic tetrominoes spawn flat side up, centered and
+/// rounded to the right, in the first visible row
+///
+/// # Arguments
+///
+/// * `kind` - The kind of the tetromino, dealt by the randomizer
+/// * `spawn` - The spawn rule the position and orientation are taken from
+/// * `board` - The board the tetromino is centered on
+pub fn create_tetronimo(kind: PieceKind, spawn: SpawnRule, board: &Board) -> Tetromino {
+    let rotation = match (spawn, kind) {
+        (SpawnRule::Classic, PieceKind::T | PieceKind::J | PieceKind::L) => 180,
+        _ => 0,
+    };
 
-pub fn create_tetronimo(current_tetromino: &mut Tetromino) {
-    let random_number: i32 = random_tetronimo();
-    let x_position: i32 = random_tetromino_position();
-    let mut tetromino_shape: Tetromino = Tetromino::blank_tetromino(x_position);
+    let cells = kind.cells(rotation);
+    let top = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    let bottom = cells.iter().map(|&(_, y)| y).max().unwrap_or(0);
 
-    match random_number {
-        1 => {
-            tetromino_shape.first_line.characters =
-                Line::create_characters(x_position, tetromino_shape.first_line.y, 4);
-            tetromino_shape.shape_type = 1;
-        }
-        2 => {
-            tetromino_shape.first_line.characters =
-                Line::create_characters(x_position, tetromino_shape.first_line.y, 3);
-            tetromino_shape.second_line.characters =
-                Line::create_characters(x_position + 2, tetromino_shape.first_line.y + 1, 1);
-            tetromino_shape.second_line.x = x_position + 2;
-            tetromino_shape.shape_type = 2;
-        }
-        3 => {
-            tetromino_shape.first_line.characters =
-                Line::create_characters(x_position, tetromino_shape.first_line.y, 2);
-            tetromino_shape.second_line.characters =
-                Line::create_characters(x_position, tetromino_shape.first_line.y + 1, 2);
-            tetromino_shape.second_line.x = x_position;
-            tetromino_shape.shape_type = 3;
-        }
-        4 => {
-            tetromino_shape.first_line.characters =
-                Line::create_characters(x_position, tetromino_shape.first_line.y, 2);
-            tetromino_shape.second_line.characters =
-                Line::create_characters(x_position + 1, tetromino_shape.first_line.y + 1, 2);
-            tetromino_shape.second_line.x = x_position + 1;
-            tetromino_shape.shape_type = 4;
-        }
-        5 => {
-            tetromino_shape.first_line.characters =
-                Line::create_characters(x_position, tetromino_shape.first_line.y, 3);
-            tetromino_shape.second_line.characters =
-                Line::create_characters(x_position + 1, tetromino_shape.first_line.y + 1, 1);
-            tetromino_shape.second_line.x = x_position + 1;
-            tetromino_shape.shape_type = 5;
-        }
-        _ => panic!("Invalid tetromino shape"),
-    }
+    let size = kind.box_size();
+    let x_position = 1 + (board.width() as i32 - size) / 2;
+    let hidden_rows = board.hidden_height() as i32;
 
-    *current_tetromino = tetromino_shape;
-}
+    let (x_position, y_position) = match spawn {
+        SpawnRule::Guideline => (x_position, hidden_rows - 1 - bottom),
+        SpawnRule::Classic => (x_position + size % 2, hidden_rows - top),
+    };
+
+    let mut tetromino = Tetromino::new(kind, x_position, y_position);
+    tetromino.rotation = rotation;
 
-fn random_tetronimo() -> i32 {
-    return rand::thread_rng().gen_range(1..=5) as i32;
+    tetromino
 }
 
-fn random_tetromino_position() -> i32 {
-    return rand::thread_rng().gen_range(2..WIDTH - 4) as i32;
+/// Checks if every cell of the tetromino is inside the walls and above the floor, without
+/// overlapping any of the filled cells of the board
+///
+/// # Arguments
+///
+/// * `tetromino` - The tetromino to check, at its current position and rotation
+/// * `board` - The board the tetromino is played on
+///
+/// # Returns
+///
+/// A boolean indicating if the tetromino fits
+pub fn fits(tetromino: &Tetromino, board: &Board) -> bool {
+    tetromino
+        .cells()
+        .all(|(x, y)| y >= 0 && !board.is_occupied(x, y))
 }
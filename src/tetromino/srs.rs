@@ -0,0 +1,148 @@
+use std::str::FromStr;
+
+use super::kind::PieceKind;
+
+/* Super Rotation System wall kicks */
+// Offsets are written as in the guideline tables, with positive y going up.
+// They have to be flipped before being applied to the screen, where y goes down.
+
+const JLSTZ_KICKS: [[(i32, i32); 5]; 8] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 0 -> R
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 0
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 2
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 2 -> R
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 2 -> L
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 2
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 0
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 0 -> L
+];
+
+const I_KICKS: [[(i32, i32); 5]; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // 0 -> R
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // R -> 0
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // R -> 2
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // 2 -> R
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // 2 -> L
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // L -> 2
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // L -> 0
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // 0 -> L
+];
+
+// The extended 180 degree kicks of NullpoMino's SRS, tried around the piece before falling back
+// to further offsets. Other games often call this table SRS+, not to be confused with TETR.IO's
+#[rustfmt::skip]
+const NULLPOMINO_180_KICKS: [[(i32, i32); 12]; 4] = [
+    [(0, 0), (1, 0), (2, 0), (1, 1), (2, 1), (-1, 0), (-2, 0), (-1, 1), (-2, 1), (0, -1), (3, 0), (-3, 0)], // 0 -> 2
+    [(0, 0), (0, 1), (0, 2), (-1, 1), (-1, 2), (0, -1), (0, -2), (-1, -1), (-1, -2), (1, 0), (0, 3), (0, -3)], // R -> L
+    [(0, 0), (-1, 0), (-2, 0), (-1, -1), (-2, -1), (1, 0), (2, 0), (1, -1), (2, -1), (0, 1), (-3, 0), (3, 0)], // 2 -> 0
+    [(0, 0), (0, 1), (0, 2), (1, 1), (1, 2), (0, -1), (0, -2), (1, -1), (1, -2), (-1, 0), (0, 3), (0, -3)], // L -> R
+];
+
+// The 180 degree kicks of SRS+, the rotation system of TETR.IO
+const TETRIO_180_KICKS: [[(i32, i32); 6]; 4] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)], // 0 -> 2
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],   // R -> L
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)], // 2 -> 0
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)], // L -> R
+];
+
+const NO_KICKS: [(i32, i32); 1] = [(0, 0)];
+
+/// Represents the wall kick table used by 180 degree rotations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kicks180 {
+    /// 180 degree rotations only succeed in place
+    None,
+    /// The extended 12 kick table of NullpoMino, commonly called SRS+
+    SrsPlus,
+    /// The 6 kick table of TETR.IO's own SRS+
+    Tetrio,
+}
+
+impl Kicks180 {
+    /// Every 180 degree kick table, in the order they are offered to the player
+    pub const ALL: [Kicks180; 3] = [Kicks180::None, Kicks180::SrsPlus, Kicks180::Tetrio];
+
+    /// Returns the name used to select the kick table from the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Kicks180::None => "none",
+            Kicks180::SrsPlus => "srs+",
+            Kicks180::Tetrio => "tetrio",
+        }
+    }
+}
+
+impl FromStr for Kicks180 {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Kicks180::ALL
+            .into_iter()
+            .find(|kicks| kicks.name() == name)
+            .ok_or_else(|| format!("Unknown 180 degree kick table '{}'", name))
+    }
+}
+
+/// Returns the wall kicks to try, in order, when rotating a piece between two rotation states
+///
+/// # Arguments
+///
+/// * `kind` - The kind of the piece being rotated
+/// * `from` - The current rotation in degrees
+/// * `to` - The target rotation in degrees
+/// * `kicks_180` - The table to use for 180 degree rotations
+///
+/// # Returns
+///
+/// A list of (x, y) offsets with y going up, the first one always being (0, 0)
+pub fn kicks(kind: PieceKind, from: i32, to: i32, kicks_180: Kicks180) -> &'static [(i32, i32)] {
+    let (from, to) = (from.rem_euclid(360), to.rem_euclid(360));
+
+    if kind != PieceKind::O && (to - from).rem_euclid(360) == 180 {
+        let index = (from / 90) as usize;
+
+        return match kicks_180 {
+            Kicks180::None => &NO_KICKS,
+            Kicks180::SrsPlus => &NULLPOMINO_180_KICKS[index],
+            Kicks180::Tetrio => &TETRIO_180_KICKS[index],
+        };
+    }
+
+    let table = match kind {
+        PieceKind::I => &I_KICKS,
+        PieceKind::O => return &NO_KICKS,
+        _ => &JLSTZ_KICKS,
+    };
+
+    let index = match (from, to) {
+        (0, 90) => 0,
+        (90, 0) => 1,
+        (90, 180) => 2,
+        (180, 90) => 3,
+        (180, 270) => 4,
+        (270, 180) => 5,
+        (270, 0) => 6,
+        (0, 270) => 7,
+        _ => return &NO_KICKS,
+    };
+
+    &table[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_180_kick_table_can_be_selected_by_name() {
+        for (table, length) in [
+            (Kicks180::None, 1),
+            (Kicks180::SrsPlus, 12),
+            (Kicks180::Tetrio, 6),
+        ] {
+            assert_eq!(table.name().parse(), Ok(table));
+            assert_eq!(kicks(PieceKind::T, 90, 270, table).len(), length);
+        }
+    }
+}
@@ -1,334 +1,143 @@
-use super::line::Line;
-
-const WIDTH: usize = 12; // 2 more to account for the borders
-const HEIGHT: usize = 40;
+use super::fits;
+use super::kind::PieceKind;
+use super::srs::{self, Kicks180};
+use crate::board::Board;
+
+/// Represents where a tetromino is in its life, from spawning to being locked into the stack
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceState {
+    /// There is room below the tetromino, so gravity moves it down
+    Falling,
+    /// The tetromino rests on the stack and moving it can still restart the lock delay
+    Landed,
+    /// The tetromino rests on the stack and locks once the lock delay runs out
+    Locking,
+    /// The tetromino is part of the stack and can no longer move
+    Locked,
+}
 
+/// Represents a tetromino as the cells of its kind at a given rotation, placed at an origin
 pub struct Tetromino {
-    pub first_line: Line,
-    pub second_line: Line,
-    pub third_line: Line,
-    pub fourth_line: Line,
+    pub x: i32, // Represents the left of the tetromino's bounding box
+    pub y: i32, // Represents the top of the tetromino's bounding box
     pub rotation: i32,
-    pub shape_type: i32,
-    pub stationary: bool,
+    pub last_kick: Option<(i32, usize)>, // The last rotation and its wall kick, cleared when the tetromino moves
+    pub kind: PieceKind,
+    pub state: PieceState,
+    pub lowest_row: i32, // The lowest y the tetromino has reached, which bounds the lock delay resets
 }
 
 impl Tetromino {
-    pub fn new(first_line: Line, second_line: Line, shape_type: i32) -> Tetromino {
+    /// Creates a tetromino of the given kind in its spawn state
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of the tetromino
+    /// * `x_position` - The x position of the left of the tetromino's bounding box
+    /// * `y_position` - The y position of the top of the tetromino's bounding box
+    pub fn new(kind: PieceKind, x_position: i32, y_position: i32) -> Tetromino {
         Tetromino {
-            first_line,
-            second_line,
-            third_line: Line::new(0, 0, 0),
-            fourth_line: Line::new(0, 0, 0),
+            x: x_position,
+            y: y_position,
             rotation: 0,
-            shape_type,
-            stationary: false,
+            last_kick: None,
+            kind,
+            state: PieceState::Falling,
+            lowest_row: y_position,
         }
     }
 
-    pub fn move_tetromino(
-        &mut self,
-        x_units: i32,
-        y_units: i32,
-        game_borders: &mut [[bool; WIDTH]; HEIGHT + 1],
-    ) {
-        let collides: bool = self.collides(game_borders);
+    /// Iterates over the (x, y) positions of the cells of the tetromino
+    pub fn cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.kind
+            .cells(self.rotation)
+            .iter()
+            .map(move |&(x_offset, y_offset)| (self.x + x_offset, self.y + y_offset))
+    }
 
-        if self.stationary {
-            return;
+    /// Moves the tetromino by the given units if it still fits on the board afterwards
+    ///
+    /// # Returns
+    ///
+    /// A boolean indicating if the tetromino moved
+    pub fn move_tetromino(&mut self, x_units: i32, y_units: i32, board: &Board) -> bool {
+        if self.state == PieceState::Locked {
+            return false;
         }
 
-        let collides_horizontal = self.collides_horizontal(x_units);
+        self.x += x_units;
+        self.y += y_units;
 
-        if collides {
-            self.stationary = true;
-            return;
+        if fits(self, board) {
+            self.last_kick = None;
+            self.lowest_row = self.lowest_row.max(self.y);
+            return true;
         }
 
-        if collides_horizontal {
-            return;
-        }
+        self.x -= x_units;
+        self.y -= y_units;
 
-        self.first_line.move_line(x_units, y_units);
-        self.second_line.move_line(x_units, y_units);
-        self.third_line.move_line(x_units, y_units);
-        self.fourth_line.move_line(x_units, y_units);
+        false
     }
 
-    pub fn blank_tetromino(x_position: i32) -> Tetromino {
-        Tetromino::new(Line::new(x_position, 1, 0), Line::new(0, 2, 0), 0)
+    /// Returns how many rows the tetromino can fall before landing
+    pub fn drop_distance(&self, board: &Board) -> i32 {
+        let mut distance = 0;
+
+        while self
+            .cells()
+            .all(|(x, y)| !board.is_occupied(x, y + distance + 1))
+        {
+            distance += 1;
+        }
+
+        distance
     }
 
-    /// Checks if the tetromino collides with the game borders
+    /// Rotates the tetromino around its pivot following the Super Rotation System
+    ///
+    /// Each wall kick of the rotation is tried in order until the rotated tetromino fits on the
+    /// board. If none of them fit, the tetromino is left as it was
     ///
     /// # Arguments
     ///
-    /// * `game_borders` - A vector containing the game borders
+    /// * `rotation` - The rotation in degrees, positive being clockwise
+    /// * `kicks_180` - The wall kick table used by 180 degree rotations
+    /// * `board` - The board the tetromino is played on
     ///
     /// # Returns
     ///
-    /// A boolean indicating if the tetromino collides with the game borders
-    pub fn collides(&mut self, game_borders: &mut [[bool; WIDTH]; HEIGHT + 1]) -> bool {
-        let mut collides: bool = false;
-
-        // Iterates through the y axis
-        for y in 1..game_borders.len() - 1 {
-            // Checks if the fourth line is empty (as it is not always used)
-            if !self.fourth_line.to_string().is_empty() {
-                // Iterates through the characters of the fourth line's characters
-                for character in &self.fourth_line.characters {
-                    // Iterates through the x axis
-                    for x in 1..game_borders[y].len() {
-                        // Checks if the characters X coordinate is the same as the game border's X coordinate, and if the game border at the fourth line's Y coordinate is true
-                        if character.x == x as i32
-                            && game_borders[(self.fourth_line.y + 1) as usize][x] == true
-                        {
-                            // collides is set to true
-                            collides = true;
-                            return collides;
-                        }
-                    }
-                }
-            }
-
-            // Apply the same process for the rest of the lines
-            if !self.third_line.to_string().is_empty() {
-                for character in &self.third_line.characters {
-                    for x in 1..game_borders[y].len() {
-                        if character.x == x as i32
-                            && game_borders[(self.third_line.y + 1) as usize][x] == true
-                        {
-                            collides = true;
-                            return collides;
-                        }
-                    }
-                }
-            }
-
-            if !self.second_line.to_string().is_empty() {
-                for character in &self.second_line.characters {
-                    for x in 1..game_borders[y].len() {
-                        if character.x == x as i32
-                            && game_borders[(self.second_line.y + 1) as usize][x] == true
-                        {
-                            collides = true;
-                            return collides;
-                        }
-                    }
-                }
-            }
-
-            // As the first line is always used, skip the check if it is empty
-            for character in &self.first_line.characters {
-                for x in 1..game_borders[y].len() {
-                    if character.x == x as i32
-                        && game_borders[(self.first_line.y + 1) as usize][x] == true
-                    {
-                        collides = true;
-                        return collides;
-                    }
-                }
-            }
-        }
-
-        return collides;
-    }
-
-    pub fn collides_horizontal(&mut self, x_units: i32) -> bool {
-        let lines_array = [
-            &self.first_line,
-            &self.second_line,
-            &self.third_line,
-            &self.fourth_line,
-        ];
-
-        let max = lines_array
-            .iter()
-            .enumerate()
-            .max_by_key(|&(_, line)| line.characters.len())
-            .map(|(index, _)| index)
-            .unwrap_or(0);
-
-        let mut collides_horizontal = false;
-
-        if let Some(line) = lines_array.get(max) {
-            if line.x + x_units < 1
-                || line.x + x_units > WIDTH as i32 - 1 - line.characters.len() as i32
-            {
-                collides_horizontal = true;
-            }
-        }
-
-        collides_horizontal
-    }
-
-    pub fn clear(&mut self) {
-        self.first_line.characters.clear();
-        self.second_line.characters.clear();
-        self.third_line.characters.clear();
-        self.fourth_line.characters.clear();
-    }
-
-    pub fn rotate(&mut self, rotation: i32, game_borders: &mut [[bool; WIDTH]; HEIGHT + 1]) {
-        if self.stationary {
-            return;
-        }
-
-        let mut new_rotation = self.rotation + rotation;
-        let normalized_rotation = match new_rotation % 360 {
-            r if r < 0 => r + 360,
-            r => r,
-        } % 360;
-
-        new_rotation = match normalized_rotation {
-            360 => 0,
-            r => r,
-        };
-
-        if self.shape_type == 3 {
-            return;
+    /// The index of the wall kick that was used, or None if the tetromino could not rotate
+    pub fn rotate(&mut self, rotation: i32, kicks_180: Kicks180, board: &Board) -> Option<usize> {
+        if self.state == PieceState::Locked || self.kind == PieceKind::O {
+            return None;
         }
 
         let past_rotation = self.rotation;
-        self.rotation = new_rotation;
-
-        self.rotate_shape(self.rotation);
-
-        if self.collides(game_borders) {
-            self.rotation = past_rotation;
-            self.rotate_shape(self.rotation);
-        }
-
-        if self.collides_horizontal(0) {
-            self.rotation = past_rotation;
-            self.rotate_shape(self.rotation);
+        let new_rotation = (self.rotation + rotation).rem_euclid(360);
+        let (past_x, past_y) = (self.x, self.y);
+
+        for (index, &(kick_x, kick_y)) in
+            srs::kicks(self.kind, past_rotation, new_rotation, kicks_180)
+                .iter()
+                .enumerate()
+        {
+            // The kick tables use y going up, while the screen's y goes down
+            self.x = past_x + kick_x;
+            self.y = past_y - kick_y;
+            self.rotation = new_rotation;
+
+            if fits(self, board) {
+                self.last_kick = Some((rotation, index));
+                self.lowest_row = self.lowest_row.max(self.y);
+                return Some(index);
+            }
         }
-    }
-
-    pub fn rotate_shape(&mut self, rotation: i32) {
-        match rotation {
-            90 => match self.shape_type {
-                1 => {
-                    self.clear();
 
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 1);
-                    self.second_line = Line::new(self.first_line.x, self.first_line.y + 1, 1);
-                    self.third_line = Line::new(self.first_line.x, self.first_line.y + 2, 1);
-                    self.fourth_line = Line::new(self.first_line.x, self.first_line.y + 3, 1);
-                }
-                2 => {
-                    self.clear();
+        self.x = past_x;
+        self.y = past_y;
+        self.rotation = past_rotation;
 
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 2);
-                    self.second_line = Line::new(self.first_line.x, self.first_line.y + 1, 1);
-                    self.third_line = Line::new(self.first_line.x, self.first_line.y + 2, 1);
-                }
-                4 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 1);
-                    self.second_line = Line::new(self.first_line.x - 1, self.first_line.y + 1, 2);
-                    self.third_line = Line::new(self.first_line.x - 1, self.first_line.y + 2, 1);
-                }
-                5 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x + 1, self.first_line.y, 1);
-                    self.second_line = Line::new(self.first_line.x, self.first_line.y + 1, 2);
-                    self.third_line = Line::new(self.first_line.x, self.first_line.y + 2, 1);
-                }
-                _ => {}
-            },
-            180 => match self.shape_type {
-                1 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 4);
-                }
-                2 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 1);
-                    self.second_line = Line::new(self.first_line.x, self.first_line.y + 1, 3);
-                }
-                4 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 2);
-                    self.second_line = Line::new(self.first_line.x + 1, self.first_line.y + 1, 2);
-                }
-                5 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 1);
-                    self.second_line = Line::new(self.first_line.x - 1, self.first_line.y + 1, 3);
-                }
-                _ => {}
-            },
-            270 => match self.shape_type {
-                1 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 1);
-                    self.second_line = Line::new(self.first_line.x, self.first_line.y + 1, 1);
-                    self.third_line = Line::new(self.first_line.x, self.first_line.y + 2, 1);
-                    self.fourth_line = Line::new(self.first_line.x, self.first_line.y + 3, 1);
-                }
-                2 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 1);
-                    self.second_line = Line::new(self.first_line.x, self.first_line.y + 1, 1);
-                    self.third_line = Line::new(self.first_line.x, self.first_line.y + 2, 2);
-                }
-                4 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 1);
-                    self.second_line = Line::new(self.first_line.x - 1, self.first_line.y + 1, 2);
-                    self.third_line = Line::new(self.first_line.x - 1, self.first_line.y + 2, 1);
-                }
-                5 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 1);
-                    self.second_line = Line::new(self.first_line.x - 1, self.first_line.y + 1, 2);
-                    self.third_line = Line::new(self.first_line.x, self.first_line.y + 2, 1);
-
-                    self.second_line.x = self.first_line.x - 1;
-                    self.second_line.y = self.first_line.y + 1;
-                    self.third_line.x = self.first_line.x;
-                    self.third_line.y = self.first_line.y + 2;
-                }
-                _ => {}
-            },
-            0 => match self.shape_type {
-                1 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 4);
-                }
-                2 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 3);
-                    self.second_line = Line::new(self.first_line.x + 2, self.first_line.y + 1, 1);
-                }
-                4 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x, self.first_line.y, 2);
-                    self.second_line = Line::new(self.first_line.x + 1, self.first_line.y + 1, 2);
-                }
-                5 => {
-                    self.clear();
-
-                    self.first_line = Line::new(self.first_line.x - 1, self.first_line.y, 3);
-                    self.second_line = Line::new(self.first_line.x + 1, self.first_line.y + 1, 1);
-                }
-                _ => {}
-            },
-            _ => {}
-        }
+        None
     }
 }
@@ -0,0 +1,111 @@
+use std::time::{Duration, Instant};
+
+const MAX_LAG: Duration = Duration::from_millis(250); // Time the loop catches up on before dropping frames
+
+/// Represents how well the game loop kept up with the frame rate
+#[derive(Clone, Copy, Debug)]
+pub struct FrameStats {
+    pub frames: u64,         // Logic frames simulated
+    pub renders: u64,        // Times the screen was drawn
+    pub late_frames: u64,    // Frames simulated after the loop fell more than a frame behind
+    pub dropped_frames: u64, // Frames skipped after the loop fell too far behind to catch up
+    pub worst_render: Duration,
+    total_render: Duration,
+    started: Instant,
+}
+
+impl FrameStats {
+    fn new() -> FrameStats {
+        FrameStats {
+            frames: 0,
+            renders: 0,
+            late_frames: 0,
+            dropped_frames: 0,
+            worst_render: Duration::ZERO,
+            total_render: Duration::ZERO,
+            started: Instant::now(),
+        }
+    }
+
+    /// Records how long drawing the screen took
+    pub fn record_render(&mut self, took: Duration) {
+        self.renders += 1;
+        self.total_render += took;
+        self.worst_render = self.worst_render.max(took);
+    }
+
+    /// Returns the average time drawing the screen took
+    pub fn average_render(&self) -> Duration {
+        self.total_render
+            .checked_div(self.renders as u32)
+            .unwrap_or_default()
+    }
+
+    /// Returns the logic frames simulated per second of real time
+    pub fn frame_rate(&self) -> f64 {
+        self.frames as f64 / self.started.elapsed().as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Turns the real time passed between iterations of the game loop into a whole number of
+/// logic frames, keeping the remainder for the next iteration
+///
+/// The game always advances by frames of the same length, no matter how often the loop runs
+/// or how long drawing takes
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration, // Real time passed that has not been simulated yet
+    previous: Instant,
+    pub stats: FrameStats,
+}
+
+impl FixedTimestep {
+    /// Creates a timestep running the given number of frames per second
+    pub fn new(frame_rate: u32) -> FixedTimestep {
+        FixedTimestep {
+            step: Duration::from_secs(1) / frame_rate,
+            accumulator: Duration::ZERO,
+            previous: Instant::now(),
+            stats: FrameStats::new(),
+        }
+    }
+
+    /// Measures the time passed since the last call
+    ///
+    /// # Returns
+    ///
+    /// The number of frames to simulate to catch up with the clock
+    pub fn advance(&mut self) -> u32 {
+        let now = Instant::now();
+        self.accumulator += now - self.previous;
+        self.previous = now;
+
+        // After a long stall, such as the terminal being suspended, the missed time is dropped
+        // rather than played back all at once
+        if self.accumulator > MAX_LAG {
+            let dropped = (self.accumulator - MAX_LAG).as_nanos() / self.step.as_nanos();
+            self.stats.dropped_frames += dropped as u64;
+            self.accumulator = MAX_LAG;
+        }
+
+        let frames = (self.accumulator.as_nanos() / self.step.as_nanos()) as u32;
+        self.accumulator -= self.step * frames;
+
+        self.stats.frames += frames as u64;
+        self.stats.late_frames += frames.saturating_sub(1) as u64;
+
+        frames
+    }
+
+    /// Returns how long to wait before the next frame is due
+    pub fn until_next_frame(&self) -> Duration {
+        self.step
+            .saturating_sub(self.accumulator + self.previous.elapsed())
+    }
+
+    /// Forgets the time passed since the last call, for when the game was not running
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+        self.previous = Instant::now();
+    }
+}